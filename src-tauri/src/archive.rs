@@ -0,0 +1,572 @@
+use chrono::{DateTime, Datelike, Local, TimeZone, Timelike};
+use filetime::FileTime;
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Component, Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+use tauri::{command, AppHandle};
+use walkdir::WalkDir;
+
+use crate::jobs::{copy_with_progress, spawn_job, JobHandle};
+use crate::paths::decode_path;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveFormat {
+    Zip,
+    TarGz,
+    TarZst,
+}
+
+impl ArchiveFormat {
+    /// Guesses the archive format from the file extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".tar.zst") || name.ends_with(".tzst") {
+            Some(Self::TarZst)
+        } else {
+            None
+        }
+    }
+}
+
+/// A filesystem entry queued for archiving, with its name inside the archive
+struct PendingEntry {
+    source: PathBuf,
+    name: PathBuf,
+    metadata: fs::Metadata,
+}
+
+/// Creates an archive from the given paths as a background job and returns the job id.
+/// The format is taken from `format` or, if omitted, from the destination extension.
+#[command]
+pub fn create_archive(
+    app: AppHandle,
+    sources: Vec<String>,
+    destination: String,
+    format: Option<ArchiveFormat>,
+) -> Result<u64, String> {
+    if sources.is_empty() {
+        return Err("No files selected for archiving".to_string());
+    }
+
+    let destination = decode_path(&destination);
+    let format = format
+        .or_else(|| ArchiveFormat::from_path(&destination))
+        .ok_or(format!("Unsupported archive format: {:?}", destination))?;
+
+    if destination.exists() {
+        return Err(format!("Destination already exists: {:?}", destination));
+    }
+
+    let sources: Vec<PathBuf> = sources.iter().map(|s| decode_path(s)).collect();
+    for source in &sources {
+        if fs::symlink_metadata(source).is_err() {
+            return Err(format!("Path does not exist: {:?}", source));
+        }
+    }
+
+    Ok(spawn_job(app, "compress", move |job| {
+        let result = write_archive(job, &sources, &destination, format);
+        if result.is_err() {
+            // Don't leave a truncated archive behind
+            let _ = fs::remove_file(&destination);
+        }
+        result
+    }))
+}
+
+/// Extracts an archive into `destination` as a background job and returns the job id.
+/// Entries with absolute paths or `..` components are refused.
+#[command]
+pub fn extract_archive(
+    app: AppHandle,
+    archive_path: String,
+    destination: String,
+) -> Result<u64, String> {
+    let archive_path = decode_path(&archive_path);
+    let destination = decode_path(&destination);
+
+    if !archive_path.is_file() {
+        return Err(format!("Archive not found: {:?}", archive_path));
+    }
+    let format = ArchiveFormat::from_path(&archive_path)
+        .ok_or(format!("Unsupported archive format: {:?}", archive_path))?;
+
+    Ok(spawn_job(app, "extract", move |job| {
+        fs::create_dir_all(&destination)
+            .map_err(|e| format!("Failed to create destination folder: {:?}", e))?;
+
+        match format {
+            ArchiveFormat::Zip => extract_zip(job, &archive_path, &destination),
+            ArchiveFormat::TarGz | ArchiveFormat::TarZst => {
+                extract_tar(job, &archive_path, &destination, format)
+            }
+        }
+    }))
+}
+
+// ------------------- Compression -------------------
+
+/// Walks every source and records the entries together with their archive names.
+/// Each source is stored under its own file name, like file managers do.
+fn collect_entries(sources: &[PathBuf]) -> Result<Vec<PendingEntry>, String> {
+    let mut entries = Vec::new();
+
+    for source in sources {
+        let base = source
+            .file_name()
+            .map(PathBuf::from)
+            .ok_or(format!("Cannot archive path without a file name: {:?}", source))?;
+
+        for entry in WalkDir::new(source).follow_links(false) {
+            let entry = entry.map_err(|e| format!("Failed to read {:?}: {:?}", source, e))?;
+            let relative = entry.path().strip_prefix(source).unwrap_or(Path::new(""));
+            let metadata = entry
+                .metadata()
+                .map_err(|e| format!("Failed to read metadata of {:?}: {:?}", entry.path(), e))?;
+
+            entries.push(PendingEntry {
+                source: entry.path().to_path_buf(),
+                name: base.join(relative),
+                metadata,
+            });
+        }
+    }
+
+    Ok(entries)
+}
+
+fn write_archive(
+    job: &JobHandle,
+    sources: &[PathBuf],
+    destination: &Path,
+    format: ArchiveFormat,
+) -> Result<(), String> {
+    let entries = collect_entries(sources)?;
+    let total_bytes: u64 = entries
+        .iter()
+        .filter(|e| e.metadata.is_file())
+        .map(|e| e.metadata.len())
+        .sum();
+
+    let file = File::create(destination)
+        .map_err(|e| format!("Failed to create archive: {:?}", e))?;
+    let writer = BufWriter::new(file);
+
+    match format {
+        ArchiveFormat::Zip => write_zip(job, &entries, writer, total_bytes),
+        ArchiveFormat::TarGz => {
+            let encoder = flate2::write::GzEncoder::new(writer, flate2::Compression::default());
+            let encoder = write_tar(job, &entries, encoder, total_bytes)?;
+            encoder
+                .finish()
+                .map_err(|e| format!("Failed to finish archive: {:?}", e))?;
+            Ok(())
+        }
+        ArchiveFormat::TarZst => {
+            let encoder = zstd::stream::write::Encoder::new(writer, 0)
+                .map_err(|e| format!("Failed to create archive: {:?}", e))?;
+            let encoder = write_tar(job, &entries, encoder, total_bytes)?;
+            encoder
+                .finish()
+                .map_err(|e| format!("Failed to finish archive: {:?}", e))?;
+            Ok(())
+        }
+    }
+}
+
+/// Archive entry names always use forward slashes regardless of the platform
+fn archive_name(name: &Path) -> String {
+    name.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn write_zip<W: Write + io::Seek>(
+    job: &JobHandle,
+    entries: &[PendingEntry],
+    writer: W,
+    total_bytes: u64,
+) -> Result<(), String> {
+    let mut zip = zip::ZipWriter::new(writer);
+    let mut processed = 0u64;
+
+    for entry in entries {
+        job.check_cancelled()?;
+
+        let name = archive_name(&entry.name);
+        let mut options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated)
+            .large_file(entry.metadata.len() >= u32::MAX as u64);
+        if let Some(mtime) = entry.metadata.modified().ok().and_then(to_zip_datetime) {
+            options = options.last_modified_time(mtime);
+        }
+        if let Some(mode) = unix_mode(&entry.metadata) {
+            options = options.unix_permissions(mode);
+        }
+
+        let file_type = entry.metadata.file_type();
+        if file_type.is_dir() {
+            zip.add_directory(name, options)
+                .map_err(|e| format!("Failed to add {:?}: {:?}", entry.source, e))?;
+        } else if file_type.is_symlink() {
+            let target = fs::read_link(&entry.source)
+                .map_err(|e| format!("Failed to read link {:?}: {:?}", entry.source, e))?;
+            zip.add_symlink(name, target.to_string_lossy(), options)
+                .map_err(|e| format!("Failed to add {:?}: {:?}", entry.source, e))?;
+        } else if file_type.is_file() {
+            zip.start_file(name, options)
+                .map_err(|e| format!("Failed to add {:?}: {:?}", entry.source, e))?;
+            let mut input = File::open(&entry.source)
+                .map_err(|e| format!("Failed to open {:?}: {:?}", entry.source, e))?;
+            copy_with_progress(job, &mut input, &mut zip, &mut processed, total_bytes, &entry.source)?;
+        }
+    }
+
+    zip.finish()
+        .map_err(|e| format!("Failed to finish archive: {:?}", e))?;
+    Ok(())
+}
+
+fn write_tar<W: Write>(
+    job: &JobHandle,
+    entries: &[PendingEntry],
+    writer: W,
+    total_bytes: u64,
+) -> Result<W, String> {
+    let mut builder = tar::Builder::new(writer);
+    let mut processed = 0u64;
+
+    for entry in entries {
+        job.check_cancelled()?;
+
+        let mut header = tar::Header::new_gnu();
+        header.set_metadata(&entry.metadata);
+
+        let file_type = entry.metadata.file_type();
+        let result = if file_type.is_symlink() {
+            let target = fs::read_link(&entry.source)
+                .map_err(|e| format!("Failed to read link {:?}: {:?}", entry.source, e))?;
+            builder.append_link(&mut header, &entry.name, target)
+        } else if file_type.is_file() {
+            let input = File::open(&entry.source)
+                .map_err(|e| format!("Failed to open {:?}: {:?}", entry.source, e))?;
+            let reader = ProgressReader {
+                inner: input,
+                job,
+                processed: &mut processed,
+                total: total_bytes,
+                path: &entry.source,
+            };
+            builder.append_data(&mut header, &entry.name, reader)
+        } else {
+            builder.append_data(&mut header, &entry.name, io::empty())
+        };
+        result.map_err(|e| {
+            job_error(job, format!("Failed to add {:?}: {:?}", entry.source, e))
+        })?;
+    }
+
+    builder
+        .into_inner()
+        .map_err(|e| format!("Failed to finish archive: {:?}", e))
+}
+
+/// Error of a job step. A step that failed because `ProgressReader` aborted it
+/// reports the cancellation rather than the wrapped read error.
+fn job_error(job: &JobHandle, message: String) -> String {
+    match job.check_cancelled() {
+        Ok(()) => message,
+        Err(cancelled) => cancelled,
+    }
+}
+
+/// Reader adapter that reports progress and aborts on cancellation
+struct ProgressReader<'a, R> {
+    inner: R,
+    job: &'a JobHandle,
+    processed: &'a mut u64,
+    total: u64,
+    path: &'a Path,
+}
+
+impl<R: Read> Read for ProgressReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // Not `Interrupted`: `io::copy`, used by tar, retries on it and would never return
+        if self.job.is_cancelled() {
+            return Err(io::Error::other("Job was cancelled"));
+        }
+        let read = self.inner.read(buf)?;
+        *self.processed += read as u64;
+        self.job.progress(*self.processed, self.total, Some(self.path));
+        Ok(read)
+    }
+}
+
+// ------------------- Extraction -------------------
+
+/// Validates an entry name from an archive and returns it as a relative path.
+/// Absolute paths, drive prefixes and `..` components are refused.
+pub fn sanitize_entry_path(name: &Path) -> Result<PathBuf, String> {
+    let mut sanitized = PathBuf::new();
+    for component in name.components() {
+        match component {
+            Component::Normal(part) => sanitized.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                return Err(format!("Refusing archive entry with '..' component: {:?}", name))
+            }
+            Component::RootDir | Component::Prefix(_) => {
+                return Err(format!("Refusing archive entry with absolute path: {:?}", name))
+            }
+        }
+    }
+
+    if sanitized.as_os_str().is_empty() {
+        return Err(format!("Refusing archive entry with empty path: {:?}", name));
+    }
+    Ok(sanitized)
+}
+
+/// Symlink targets must stay inside the extraction folder as well
+fn check_link_target(entry: &Path, target: &Path) -> Result<(), String> {
+    let escapes = target.is_absolute()
+        || target
+            .components()
+            .any(|c| matches!(c, Component::ParentDir | Component::Prefix(_)));
+    if escapes {
+        return Err(format!(
+            "Refusing symlink {:?} pointing outside the archive: {:?}",
+            entry, target
+        ));
+    }
+    Ok(())
+}
+
+fn extract_zip(job: &JobHandle, archive_path: &Path, destination: &Path) -> Result<(), String> {
+    let file = File::open(archive_path)
+        .map_err(|e| format!("Failed to open archive: {:?}", e))?;
+    let mut zip = zip::ZipArchive::new(BufReader::new(file))
+        .map_err(|e| format!("Failed to read archive: {:?}", e))?;
+
+    let mut total_bytes = 0u64;
+    for i in 0..zip.len() {
+        if let Ok(entry) = zip.by_index_raw(i) {
+            total_bytes += entry.size();
+        }
+    }
+
+    let mut processed = 0u64;
+    // Directory times and modes are applied last, after their children have been written
+    let mut directories = Vec::new();
+
+    for i in 0..zip.len() {
+        job.check_cancelled()?;
+
+        let mut entry = zip
+            .by_index(i)
+            .map_err(|e| format!("Failed to read archive entry: {:?}", e))?;
+        let relative = sanitize_entry_path(Path::new(entry.name()))?;
+        let target = destination.join(&relative);
+        let mode = entry.unix_mode();
+        let mtime = entry.last_modified().and_then(from_zip_datetime);
+
+        if entry.is_dir() {
+            fs::create_dir_all(&target)
+                .map_err(|e| format!("Failed to create {:?}: {:?}", target, e))?;
+            directories.push((target, mode, mtime));
+            continue;
+        }
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {:?}: {:?}", parent, e))?;
+        }
+
+        if entry.is_symlink() {
+            let mut link_target = String::new();
+            entry
+                .read_to_string(&mut link_target)
+                .map_err(|e| format!("Failed to read link {:?}: {:?}", relative, e))?;
+            check_link_target(&relative, Path::new(&link_target))?;
+            create_symlink(Path::new(&link_target), &target)?;
+            continue;
+        }
+
+        let mut output = File::create(&target)
+            .map_err(|e| format!("Failed to create {:?}: {:?}", target, e))?;
+        copy_with_progress(job, &mut entry, &mut output, &mut processed, total_bytes, &relative)?;
+        drop(output);
+
+        apply_mode(&target, mode)?;
+        if let Some(mtime) = mtime {
+            let _ = filetime::set_file_mtime(&target, mtime);
+        }
+    }
+
+    for (path, mode, mtime) in directories.iter().rev() {
+        apply_mode(path, *mode)?;
+        if let Some(mtime) = mtime {
+            let _ = filetime::set_file_mtime(path, *mtime);
+        }
+    }
+
+    job.progress(total_bytes, total_bytes, None);
+    Ok(())
+}
+
+fn extract_tar(
+    job: &JobHandle,
+    archive_path: &Path,
+    destination: &Path,
+    format: ArchiveFormat,
+) -> Result<(), String> {
+    let file = File::open(archive_path)
+        .map_err(|e| format!("Failed to open archive: {:?}", e))?;
+    let total_bytes = file.metadata().map(|m| m.len()).unwrap_or(0);
+
+    // Progress is measured on the compressed stream, whose size is known up front
+    let mut processed = 0u64;
+    let reader = ProgressReader {
+        inner: BufReader::new(file),
+        job,
+        processed: &mut processed,
+        total: total_bytes,
+        path: archive_path,
+    };
+
+    let decoder: Box<dyn Read + '_> = match format {
+        ArchiveFormat::TarGz => Box::new(flate2::read::GzDecoder::new(reader)),
+        ArchiveFormat::TarZst => Box::new(
+            zstd::stream::read::Decoder::new(reader)
+                .map_err(|e| format!("Failed to read archive: {:?}", e))?,
+        ),
+        ArchiveFormat::Zip => unreachable!("zip archives are handled by extract_zip"),
+    };
+
+    let mut archive = tar::Archive::new(decoder);
+    archive.set_preserve_permissions(true);
+    archive.set_preserve_mtime(true);
+
+    let mut directories = Vec::new();
+    let entries = archive
+        .entries()
+        .map_err(|e| job_error(job, format!("Failed to read archive: {:?}", e)))?;
+
+    for entry in entries {
+        job.check_cancelled()?;
+
+        let mut entry = entry
+            .map_err(|e| job_error(job, format!("Failed to read archive entry: {:?}", e)))?;
+        let name = entry
+            .path()
+            .map_err(|e| format!("Invalid archive entry name: {:?}", e))?
+            .into_owned();
+        let relative = sanitize_entry_path(&name)?;
+        let target = destination.join(&relative);
+
+        let entry_type = entry.header().entry_type();
+        if entry_type.is_symlink() || entry_type.is_hard_link() {
+            let link_target = entry
+                .link_name()
+                .map_err(|e| format!("Invalid link in archive: {:?}", e))?
+                .ok_or(format!("Link without target in archive: {:?}", name))?;
+            check_link_target(&relative, &link_target)?;
+        }
+
+        if entry_type.is_dir() {
+            if let Ok(mtime) = entry.header().mtime() {
+                directories.push((target, FileTime::from_unix_time(mtime as i64, 0)));
+            }
+        }
+
+        // unpack_in resolves hard link targets against the destination folder
+        entry
+            .unpack_in(destination)
+            .map_err(|e| job_error(job, format!("Failed to extract {:?}: {:?}", relative, e)))?;
+    }
+
+    for (path, mtime) in directories.iter().rev() {
+        let _ = filetime::set_file_mtime(path, *mtime);
+    }
+
+    job.progress(total_bytes, total_bytes, None);
+    Ok(())
+}
+
+// ------------------- Platform helpers -------------------
+
+#[cfg(unix)]
+fn unix_mode(metadata: &fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(metadata.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+fn unix_mode(_metadata: &fs::Metadata) -> Option<u32> {
+    None
+}
+
+#[cfg(unix)]
+fn apply_mode(path: &Path, mode: Option<u32>) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+    if let Some(mode) = mode {
+        fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o7777))
+            .map_err(|e| format!("Failed to set permissions of {:?}: {:?}", path, e))?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn apply_mode(_path: &Path, _mode: Option<u32>) -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(unix)]
+fn create_symlink(target: &Path, link: &Path) -> Result<(), String> {
+    std::os::unix::fs::symlink(target, link)
+        .map_err(|e| format!("Failed to create link {:?}: {:?}", link, e))
+}
+
+#[cfg(windows)]
+fn create_symlink(target: &Path, link: &Path) -> Result<(), String> {
+    std::os::windows::fs::symlink_file(target, link)
+        .map_err(|e| format!("Failed to create link {:?}: {:?}", link, e))
+}
+
+/// Zip stores local wall-clock time with two second precision
+fn to_zip_datetime(time: std::time::SystemTime) -> Option<zip::DateTime> {
+    let secs = time.duration_since(UNIX_EPOCH).ok()?.as_secs() as i64;
+    let local = DateTime::from_timestamp(secs, 0)?.with_timezone(&Local);
+    zip::DateTime::from_date_and_time(
+        u16::try_from(local.year()).ok()?,
+        local.month() as u8,
+        local.day() as u8,
+        local.hour() as u8,
+        local.minute() as u8,
+        local.second() as u8,
+    )
+    .ok()
+}
+
+fn from_zip_datetime(time: zip::DateTime) -> Option<FileTime> {
+    let local = Local
+        .with_ymd_and_hms(
+            time.year() as i32,
+            time.month() as u32,
+            time.day() as u32,
+            time.hour() as u32,
+            time.minute() as u32,
+            time.second() as u32,
+        )
+        .earliest()?;
+    Some(FileTime::from_unix_time(local.timestamp(), 0))
+}
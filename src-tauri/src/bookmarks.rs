@@ -0,0 +1,227 @@
+use once_cell::sync::Lazy;
+use std::{collections::HashSet, path::PathBuf, sync::Mutex};
+use tauri::{command, AppHandle};
+
+use crate::config;
+use crate::directories::ensure_directory;
+use crate::error::ExplorerError;
+use crate::paths;
+
+/// Version of `bookmarks.json`
+pub const BOOKMARKS_VERSION: u32 = 1;
+const BOOKMARKS_FILE: &str = "bookmarks.json";
+
+// Loaded from disk on first use
+static BOOKMARKS: Lazy<Mutex<Option<BookmarkStore>>> = Lazy::new(|| Mutex::new(None));
+
+/// Pinned folder as stored in `bookmarks.json`
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct StoredBookmark {
+    pub id: u64,
+    pub path: String, // encoded with `paths::encode_path`
+    pub label: String,
+    #[serde(default)]
+    pub icon: Option<String>, // icon name chosen in the UI
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct Bookmark {
+    #[serde(flatten)]
+    pub bookmark: StoredBookmark,
+    // Checked every time bookmarks are listed; missing folders are kept, since they
+    // often live on a drive that isn't plugged in
+    pub exists: bool,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+struct BookmarksFile {
+    version: u32,
+    next_id: u64,
+    bookmarks: Vec<StoredBookmark>,
+}
+
+impl BookmarksFile {
+    fn find(&mut self, id: u64) -> Result<&mut StoredBookmark, ExplorerError> {
+        self.bookmarks
+            .iter_mut()
+            .find(|b| b.id == id)
+            .ok_or(ExplorerError::InvalidArgument(format!(
+                "Unknown bookmark {}",
+                id
+            )))
+    }
+}
+
+struct BookmarkStore {
+    path: PathBuf,
+    file: BookmarksFile,
+}
+
+impl BookmarkStore {
+    fn load(app: &AppHandle) -> Result<Self, ExplorerError> {
+        let path = config::config_path(app, BOOKMARKS_FILE)?;
+        let file = match config::read_versioned(&path)? {
+            None => BookmarksFile {
+                version: BOOKMARKS_VERSION,
+                next_id: 1,
+                bookmarks: Vec::new(),
+            },
+            Some((version, document)) => {
+                config::check_version(&path, version, BOOKMARKS_VERSION)?;
+                serde_json::from_value(document).map_err(|e| {
+                    ExplorerError::Unavailable(format!("Invalid {}: {}", BOOKMARKS_FILE, e))
+                })?
+            }
+        };
+        Ok(Self { path, file })
+    }
+
+    /// Writes a changed copy of the bookmarks and only keeps it once it is on disk,
+    /// so a failed write leaves memory matching the file
+    fn save(&mut self, mut file: BookmarksFile) -> Result<(), ExplorerError> {
+        file.version = BOOKMARKS_VERSION;
+        config::write_json(&self.path, &file)?;
+        self.file = file;
+        Ok(())
+    }
+
+    fn list(&self) -> Vec<Bookmark> {
+        self.file.bookmarks.iter().map(with_status).collect()
+    }
+}
+
+fn with_status(bookmark: &StoredBookmark) -> Bookmark {
+    Bookmark {
+        exists: paths::decode_path(&bookmark.path).is_dir(),
+        bookmark: bookmark.clone(),
+    }
+}
+
+/// Runs `f` on the bookmarks, loading them on first use
+fn with_store<T>(
+    app: &AppHandle,
+    f: impl FnOnce(&mut BookmarkStore) -> Result<T, ExplorerError>,
+) -> Result<T, ExplorerError> {
+    let mut store = BOOKMARKS.lock().unwrap();
+    if store.is_none() {
+        *store = Some(BookmarkStore::load(app)?);
+    }
+    match store.as_mut() {
+        Some(store) => f(store),
+        None => Err(ExplorerError::Internal("Bookmarks not loaded".to_string())),
+    }
+}
+
+/// Loads the bookmarks at startup. Folders that are gone are kept, `list_bookmarks`
+/// flags them through `exists`.
+pub fn init(app: &AppHandle) {
+    if let Err(e) = with_store(app, |_| Ok(())) {
+        config::report(
+            app,
+            BOOKMARKS_FILE,
+            format!("Failed to load bookmarks: {}", e),
+        );
+    }
+}
+
+#[command]
+pub fn list_bookmarks(app: AppHandle) -> Result<Vec<Bookmark>, ExplorerError> {
+    with_store(&app, |store| Ok(store.list()))
+}
+
+/// Pins a folder at the end of the list. The label defaults to the folder name.
+#[command]
+pub fn add_bookmark(
+    app: AppHandle,
+    path: String,
+    label: Option<String>,
+    icon: Option<String>,
+) -> Result<Bookmark, ExplorerError> {
+    let folder = paths::decode_path(&path);
+    ensure_directory(&folder)?;
+    let path = paths::encode_path(&folder);
+    let label = label
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .unwrap_or_else(|| match paths::file_name(&folder) {
+            name if name.is_empty() => path.clone(),
+            name => name,
+        });
+
+    with_store(&app, |store| {
+        if store.file.bookmarks.iter().any(|b| b.path == path) {
+            return Err(ExplorerError::AlreadyExists(path));
+        }
+        let mut file = store.file.clone();
+        let bookmark = StoredBookmark {
+            id: file.next_id,
+            path,
+            label,
+            icon,
+        };
+        file.next_id += 1;
+        file.bookmarks.push(bookmark.clone());
+        store.save(file)?;
+        Ok(with_status(&bookmark))
+    })
+}
+
+/// Changes the label and icon of a bookmark. `icon` replaces the current one, None removes it.
+#[command]
+pub fn update_bookmark(
+    app: AppHandle,
+    id: u64,
+    label: Option<String>,
+    icon: Option<String>,
+) -> Result<Bookmark, ExplorerError> {
+    with_store(&app, |store| {
+        let mut file = store.file.clone();
+        let bookmark = file.find(id)?;
+        if let Some(label) = label.map(|l| l.trim().to_string()) {
+            if label.is_empty() {
+                return Err(ExplorerError::InvalidArgument(
+                    "Bookmark label can't be empty".to_string(),
+                ));
+            }
+            bookmark.label = label;
+        }
+        bookmark.icon = icon;
+        let updated = with_status(bookmark);
+        store.save(file)?;
+        Ok(updated)
+    })
+}
+
+/// Returns false if no bookmark has this id
+#[command]
+pub fn remove_bookmark(app: AppHandle, id: u64) -> Result<bool, ExplorerError> {
+    with_store(&app, |store| {
+        let mut file = store.file.clone();
+        file.bookmarks.retain(|b| b.id != id);
+        if file.bookmarks.len() == store.file.bookmarks.len() {
+            return Ok(false);
+        }
+        store.save(file)?;
+        Ok(true)
+    })
+}
+
+/// Puts the bookmarks in the order of `ids`, which must list every bookmark exactly once
+#[command]
+pub fn reorder_bookmarks(app: AppHandle, ids: Vec<u64>) -> Result<Vec<Bookmark>, ExplorerError> {
+    with_store(&app, |store| {
+        let current: HashSet<u64> = store.file.bookmarks.iter().map(|b| b.id).collect();
+        let requested: HashSet<u64> = ids.iter().copied().collect();
+        if ids.len() != current.len() || requested != current {
+            return Err(ExplorerError::InvalidArgument(
+                "The new order must list every bookmark exactly once".to_string(),
+            ));
+        }
+
+        let mut file = store.file.clone();
+        file.bookmarks
+            .sort_by_key(|b| ids.iter().position(|id| *id == b.id));
+        store.save(file)?;
+        Ok(store.list())
+    })
+}
@@ -0,0 +1,409 @@
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+};
+use tauri::{command, AppHandle, Emitter};
+use walkdir::WalkDir;
+
+use crate::jobs::spawn_job;
+use crate::paths::{decode_path, encode_path};
+
+/// Event emitted for every file hashed by a checksum job
+pub const CHECKSUM_RESULT_EVENT: &str = "checksum-result";
+/// Event emitted for every entry checked by a verification job
+pub const CHECKSUM_VERIFIED_EVENT: &str = "checksum-verified";
+/// Event emitted by a verification job for every checksum list that couldn't be read
+pub const CHECKSUM_LIST_FAILED_EVENT: &str = "checksum-list-failed";
+
+const HASH_BUFFER_SIZE: usize = 1024 * 1024;
+
+#[derive(
+    serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ChecksumAlgorithm {
+    Blake3,
+    Sha256,
+    Sha1,
+    Md5,
+}
+
+impl ChecksumAlgorithm {
+    /// Recognises `SHA256SUMS`-style list names and `.sha256`-style sidecar extensions
+    fn from_sums_file(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        let stem = name.strip_suffix(".txt").unwrap_or(&name);
+        [
+            (Self::Blake3, "b3sums", ".b3"),
+            (Self::Sha256, "sha256sums", ".sha256"),
+            (Self::Sha1, "sha1sums", ".sha1"),
+            (Self::Md5, "md5sums", ".md5"),
+        ]
+        .into_iter()
+        .find(|(_, list, ext)| stem == *list || name.ends_with(ext))
+        .map(|(algorithm, _, _)| algorithm)
+    }
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct FileChecksums {
+    pub job_id: u64,
+    pub path: String,
+    pub checksums: BTreeMap<ChecksumAlgorithm, String>, // lowercase hex
+    pub error: Option<String>,
+}
+
+#[derive(serde::Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum VerifyStatus {
+    Match,
+    Mismatch,
+    Missing,
+    Unreadable,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct ChecksumVerification {
+    pub job_id: u64,
+    pub checksum_file: String,
+    pub path: String,
+    pub algorithm: ChecksumAlgorithm,
+    pub expected: String,
+    pub actual: Option<String>,
+    pub status: VerifyStatus,
+    pub error: Option<String>,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct ChecksumListError {
+    pub job_id: u64,
+    pub checksum_file: String,
+    pub error: String,
+}
+
+/// One line of a checksum list
+struct ExpectedChecksum {
+    checksum_file: PathBuf,
+    path: PathBuf,
+    algorithm: ChecksumAlgorithm,
+    expected: String,
+}
+
+/// Hashes the given files, and every file inside the given folders, as a background job.
+/// Results are emitted per file through `checksum-result` events; a file that can't be
+/// read is reported there and doesn't stop the job.
+#[command]
+pub fn compute_checksums(
+    app: AppHandle,
+    paths: Vec<String>,
+    algorithms: Vec<ChecksumAlgorithm>,
+) -> Result<u64, String> {
+    if paths.is_empty() {
+        return Err("No files selected".to_string());
+    }
+    if algorithms.is_empty() {
+        return Err("No checksum algorithm selected".to_string());
+    }
+
+    let emitter = app.clone();
+    Ok(spawn_job(app, "checksum", move |job| {
+        let files: Vec<(PathBuf, u64)> = paths
+            .iter()
+            .flat_map(|path| WalkDir::new(decode_path(path)).follow_links(false))
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .map(|entry| {
+                let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+                (entry.into_path(), size)
+            })
+            .collect();
+        let total_bytes = files.iter().map(|(_, size)| size).sum();
+        let mut processed = 0u64;
+
+        for (path, size) in files {
+            let processed_before = processed;
+            let result = digest_file(&path, &algorithms, |read| {
+                job.check_cancelled()?;
+                processed += read;
+                job.progress(processed, total_bytes, Some(&path));
+                Ok(())
+            });
+            job.check_cancelled()?;
+            // Keep the total consistent if the file changed size while being read
+            processed = processed_before + size;
+
+            let (checksums, error) = match result {
+                Ok(checksums) => (checksums, None),
+                Err(e) => (BTreeMap::new(), Some(e)),
+            };
+            let _ = emitter.emit(
+                CHECKSUM_RESULT_EVENT,
+                FileChecksums {
+                    job_id: job.id(),
+                    path: encode_path(&path),
+                    checksums,
+                    error,
+                },
+            );
+        }
+        Ok(())
+    }))
+}
+
+/// Checks the files listed in the `SHA256SUMS`, `*.sha256` (and SHA1/MD5/BLAKE3
+/// equivalents) files of a folder as a background job.
+/// Every listed entry is reported through a `checksum-verified` event. A list that can't
+/// be read is reported through a `checksum-list-failed` event and the others are still
+/// checked; the command only fails when none of them could be read.
+#[command]
+pub fn verify_checksums(app: AppHandle, directory: String) -> Result<u64, String> {
+    let directory = decode_path(&directory);
+    let entries = fs::read_dir(&directory)
+        .map_err(|e| format!("Failed to read directory {:?}: {:?}", directory, e))?;
+
+    let mut expected = Vec::new();
+    let mut failed_lists = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if let Some(algorithm) = ChecksumAlgorithm::from_sums_file(&path) {
+            if path.is_file() {
+                match read_sums_file(&path, algorithm) {
+                    Ok(entries) => expected.extend(entries),
+                    Err(e) => failed_lists.push((path, e)),
+                }
+            }
+        }
+    }
+    if expected.is_empty() {
+        return Err(match failed_lists.into_iter().next() {
+            Some((_, error)) => error,
+            None => format!("No checksum files found in {:?}", directory),
+        });
+    }
+
+    let emitter = app.clone();
+    Ok(spawn_job(app, "verify-checksum", move |job| {
+        for (checksum_file, error) in failed_lists {
+            let _ = emitter.emit(
+                CHECKSUM_LIST_FAILED_EVENT,
+                ChecksumListError {
+                    job_id: job.id(),
+                    checksum_file: encode_path(&checksum_file),
+                    error,
+                },
+            );
+        }
+
+        let total_bytes = expected
+            .iter()
+            .filter_map(|e| fs::metadata(&e.path).ok())
+            .map(|m| m.len())
+            .sum();
+        let mut processed = 0u64;
+
+        for entry in expected {
+            job.check_cancelled()?;
+            let (actual, status, error) = if !entry.path.exists() {
+                (None, VerifyStatus::Missing, None)
+            } else {
+                let result = digest_file(&entry.path, &[entry.algorithm], |read| {
+                    job.check_cancelled()?;
+                    processed += read;
+                    job.progress(processed, total_bytes, Some(&entry.path));
+                    Ok(())
+                });
+                job.check_cancelled()?;
+                match result.map(|mut c| c.remove(&entry.algorithm)) {
+                    Ok(Some(actual)) => {
+                        let status = if actual == entry.expected {
+                            VerifyStatus::Match
+                        } else {
+                            VerifyStatus::Mismatch
+                        };
+                        (Some(actual), status, None)
+                    }
+                    Ok(None) => (None, VerifyStatus::Unreadable, None),
+                    Err(e) => (None, VerifyStatus::Unreadable, Some(e)),
+                }
+            };
+
+            let _ = emitter.emit(
+                CHECKSUM_VERIFIED_EVENT,
+                ChecksumVerification {
+                    job_id: job.id(),
+                    checksum_file: encode_path(&entry.checksum_file),
+                    path: encode_path(&entry.path),
+                    algorithm: entry.algorithm,
+                    expected: entry.expected,
+                    actual,
+                    status,
+                    error,
+                },
+            );
+        }
+        Ok(())
+    }))
+}
+
+/// Hashes a file with several algorithms in a single pass.
+/// `on_read` is called with the size of every block read and can abort by returning an error.
+pub fn digest_file(
+    path: &Path,
+    algorithms: &[ChecksumAlgorithm],
+    mut on_read: impl FnMut(u64) -> Result<(), String>,
+) -> Result<BTreeMap<ChecksumAlgorithm, String>, String> {
+    let mut file = File::open(path).map_err(|e| format!("Failed to open {:?}: {:?}", path, e))?;
+    let mut hashers: Vec<Hasher> = algorithms.iter().map(|&a| Hasher::new(a)).collect();
+    let mut buffer = vec![0u8; HASH_BUFFER_SIZE];
+
+    loop {
+        let read = match file.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(format!("Failed to read {:?}: {:?}", path, e)),
+        };
+        for hasher in &mut hashers {
+            hasher.update(&buffer[..read]);
+        }
+        on_read(read as u64)?;
+    }
+
+    Ok(algorithms
+        .iter()
+        .copied()
+        .zip(hashers.into_iter().map(Hasher::finalize_hex))
+        .collect())
+}
+
+enum Hasher {
+    Blake3(Box<blake3::Hasher>),
+    Sha256(Sha256),
+    Sha1(Sha1),
+    Md5(Md5),
+}
+
+impl Hasher {
+    fn new(algorithm: ChecksumAlgorithm) -> Self {
+        match algorithm {
+            ChecksumAlgorithm::Blake3 => Self::Blake3(Box::default()),
+            ChecksumAlgorithm::Sha256 => Self::Sha256(Sha256::new()),
+            ChecksumAlgorithm::Sha1 => Self::Sha1(Sha1::new()),
+            ChecksumAlgorithm::Md5 => Self::Md5(Md5::new()),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Blake3(hasher) => {
+                hasher.update(data);
+            }
+            Self::Sha256(hasher) => hasher.update(data),
+            Self::Sha1(hasher) => hasher.update(data),
+            Self::Md5(hasher) => hasher.update(data),
+        }
+    }
+
+    fn finalize_hex(self) -> String {
+        match self {
+            Self::Blake3(hasher) => hasher.finalize().to_hex().to_string(),
+            Self::Sha256(hasher) => to_hex(&hasher.finalize()),
+            Self::Sha1(hasher) => to_hex(&hasher.finalize()),
+            Self::Md5(hasher) => to_hex(&hasher.finalize()),
+        }
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// ------------------- Checksum lists -------------------
+
+/// Parses GNU (`<hash>  <name>`, `<hash> *<name>`) and BSD (`SHA256 (<name>) = <hash>`)
+/// lines. A sidecar holding only a hash applies to the file named like it without
+/// the extension, e.g. `image.iso.sha256` for `image.iso`.
+fn read_sums_file(
+    path: &Path,
+    algorithm: ChecksumAlgorithm,
+) -> Result<Vec<ExpectedChecksum>, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read {:?}: {:?}", path, e))?;
+    let directory = path.parent().unwrap_or(Path::new(""));
+
+    let mut entries = Vec::new();
+    for line in content.lines() {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let Some((hash, name)) = parse_sums_line(line).or_else(|| {
+            let name = path.file_stem()?.to_string_lossy().into_owned();
+            is_hex(line).then(|| (line.to_string(), name))
+        }) else {
+            continue;
+        };
+
+        entries.push(ExpectedChecksum {
+            checksum_file: path.to_path_buf(),
+            path: directory.join(name),
+            algorithm,
+            expected: hash.to_lowercase(),
+        });
+    }
+    Ok(entries)
+}
+
+fn parse_sums_line(line: &str) -> Option<(String, String)> {
+    // BSD style: `SHA256 (file.iso) = abc...`
+    if let Some((left, hash)) = line.rsplit_once(") = ") {
+        let (_, name) = left.split_once(" (")?;
+        return is_hex(hash).then(|| (hash.to_string(), name.to_string()));
+    }
+
+    // GNU style, where a leading backslash means the name has `\\` and `\n` escapes
+    let (escaped, line) = match line.strip_prefix('\\') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let (hash, name) = line.split_once(' ')?;
+    let name = name.strip_prefix([' ', '*']).unwrap_or(name);
+    if !is_hex(hash) || name.is_empty() {
+        return None;
+    }
+    let name = if escaped {
+        unescape_name(name)
+    } else {
+        name.to_string()
+    };
+    Some((hash.to_string(), name))
+}
+
+fn unescape_name(name: &str) -> String {
+    let mut unescaped = String::with_capacity(name.len());
+    let mut chars = name.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                unescaped.push('\n');
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                unescaped.push('\\');
+                chars.next();
+            }
+            _ => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+fn is_hex(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|b| b.is_ascii_hexdigit())
+}
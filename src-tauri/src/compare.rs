@@ -0,0 +1,489 @@
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    fs::{self, File},
+    path::{Component, Path, PathBuf},
+};
+use tauri::{command, AppHandle};
+
+use crate::checksums::{digest_file, ChecksumAlgorithm};
+use crate::jobs::{copy_with_progress, spawn_job, JobHandle};
+use crate::models::{epoch_seconds, FileKind};
+use crate::paths::{decode_path, display_name, encode_path};
+
+/// FAT and some network filesystems only store modification times with 2 second precision
+const MTIME_TOLERANCE_SECS: i64 = 2;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CompareStatus {
+    OnlyLeft,
+    OnlyRight,
+    Identical,
+    Different,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct EntrySide {
+    pub kind: FileKind,
+    pub size: u64,
+    pub modified: Option<i64>,
+}
+
+/// One entry of the comparison tree. Folders present on only one side, or facing a file,
+/// still list their children so the side-by-side view can expand them.
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct CompareNode {
+    pub name: String,
+    pub relative_path: String,
+    pub status: CompareStatus,
+    pub left: Option<EntrySide>,
+    pub right: Option<EntrySide>,
+    pub error: Option<String>,
+    pub children: Vec<CompareNode>,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct DirectoryComparison {
+    pub root: CompareNode,
+    // Counts of non-folder entries
+    pub only_left: usize,
+    pub only_right: usize,
+    pub identical: usize,
+    pub different: usize,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncOperation {
+    CreateDirectory,
+    Copy,
+    Update,
+    Delete, // removes folders recursively
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct SyncAction {
+    pub operation: SyncOperation,
+    pub relative_path: String,
+    pub bytes: u64,
+}
+
+/// Steps making `destination` a copy of `source`, in execution order
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct SyncPlan {
+    pub source: String,
+    pub destination: String,
+    pub actions: Vec<SyncAction>,
+    pub total_bytes: u64,
+}
+
+/// Walks both folders and classifies every entry.
+/// Files are compared by size and modification time, or by size and blake3 hash when
+/// `compare_content` is set.
+#[command]
+pub async fn compare_directories(
+    left: String,
+    right: String,
+    compare_content: Option<bool>,
+) -> Result<DirectoryComparison, String> {
+    let compare_content = compare_content.unwrap_or(false);
+    tokio::task::spawn_blocking(move || {
+        let left = decode_path(&left);
+        let right = decode_path(&right);
+        for dir in [&left, &right] {
+            if !dir.is_dir() {
+                return Err(format!("Not a directory: {:?}", dir));
+            }
+        }
+
+        let root = compare_roots(&left, &right, compare_content);
+        let [only_left, only_right, identical, different] = count_entries(&root);
+        Ok(DirectoryComparison {
+            root,
+            only_left,
+            only_right,
+            identical,
+            different,
+        })
+    })
+    .await
+    .map_err(|e| format!("Task join error: {:?}", e))?
+}
+
+/// Builds the one-way plan copying new and changed entries from `source` to `destination`.
+/// Entries only present in `destination` are deleted when `delete_extra` is set.
+/// The destination folder doesn't need to exist yet.
+#[command]
+pub async fn plan_sync(
+    source: String,
+    destination: String,
+    delete_extra: Option<bool>,
+    compare_content: Option<bool>,
+) -> Result<SyncPlan, String> {
+    tokio::task::spawn_blocking(move || {
+        let source_path = decode_path(&source);
+        if !source_path.is_dir() {
+            return Err(format!("Not a directory: {:?}", source_path));
+        }
+        let destination_path = &decode_path(&destination);
+        if destination_path.exists() && !destination_path.is_dir() {
+            return Err(format!("Not a directory: {:?}", destination_path));
+        }
+
+        let root = compare_roots(
+            &source_path,
+            destination_path,
+            compare_content.unwrap_or(false),
+        );
+        let mut actions = Vec::new();
+        if root.right.is_none() {
+            actions.push(SyncAction {
+                operation: SyncOperation::CreateDirectory,
+                relative_path: String::new(),
+                bytes: 0,
+            });
+        }
+        for child in &root.children {
+            plan_node(child, delete_extra.unwrap_or(false), &mut actions);
+        }
+
+        Ok(SyncPlan {
+            source,
+            destination,
+            total_bytes: actions.iter().map(|a| a.bytes).sum(),
+            actions,
+        })
+    })
+    .await
+    .map_err(|e| format!("Task join error: {:?}", e))?
+}
+
+/// Executes a plan from `plan_sync` as a background job and returns the job id
+#[command]
+pub fn execute_sync(app: AppHandle, plan: SyncPlan) -> Result<u64, String> {
+    // The plan round-trips through the frontend, never let it point outside the two roots
+    for action in &plan.actions {
+        let is_root = action.relative_path.is_empty();
+        if !(is_root && action.operation == SyncOperation::CreateDirectory) {
+            check_relative_path(&action.relative_path)?;
+        }
+    }
+
+    Ok(spawn_job(app, "sync", move |job| run_sync(job, &plan)))
+}
+
+// ------------------- Comparison -------------------
+
+fn compare_roots(left: &Path, right: &Path, compare_content: bool) -> CompareNode {
+    compare_entry(
+        String::new(),
+        PathBuf::new(),
+        side(left),
+        side(right),
+        compare_content,
+    )
+}
+
+/// Roots are followed when they are symlinks, unlike the entries below them
+fn side(path: &Path) -> Option<(PathBuf, fs::Metadata)> {
+    fs::metadata(path)
+        .ok()
+        .map(|metadata| (path.to_path_buf(), metadata))
+}
+
+fn compare_entry(
+    name: String,
+    relative: PathBuf,
+    left: Option<(PathBuf, fs::Metadata)>,
+    right: Option<(PathBuf, fs::Metadata)>,
+    compare_content: bool,
+) -> CompareNode {
+    let mut error = None;
+
+    // Children of folders on either side, by name
+    let mut children: BTreeMap<OsString, [Option<(PathBuf, fs::Metadata)>; 2]> = BTreeMap::new();
+    for (index, entry) in [&left, &right].into_iter().enumerate() {
+        let Some((path, metadata)) = entry else {
+            continue;
+        };
+        if !metadata.is_dir() {
+            continue;
+        }
+        match fs::read_dir(path) {
+            Ok(entries) => {
+                for entry in entries.flatten() {
+                    if let Ok(metadata) = entry.metadata() {
+                        children.entry(entry.file_name()).or_default()[index] =
+                            Some((entry.path(), metadata));
+                    }
+                }
+            }
+            Err(e) => error = Some(format!("Failed to read directory {:?}: {:?}", path, e)),
+        }
+    }
+
+    let children: Vec<CompareNode> = children
+        .into_iter()
+        .map(|(child_name, [child_left, child_right])| {
+            compare_entry(
+                display_name(&child_name),
+                relative.join(&child_name),
+                child_left,
+                child_right,
+                compare_content,
+            )
+        })
+        .collect();
+
+    let status = match (&left, &right) {
+        (None, _) => CompareStatus::OnlyRight,
+        (_, None) => CompareStatus::OnlyLeft,
+        _ if error.is_some() => CompareStatus::Different,
+        (Some((_, l)), Some((_, r))) if l.is_dir() && r.is_dir() => {
+            if children
+                .iter()
+                .all(|c| c.status == CompareStatus::Identical)
+            {
+                CompareStatus::Identical
+            } else {
+                CompareStatus::Different
+            }
+        }
+        (Some(l), Some(r)) => match same_content(l, r, compare_content) {
+            Ok(true) => CompareStatus::Identical,
+            Ok(false) => CompareStatus::Different,
+            Err(e) => {
+                error = Some(e);
+                CompareStatus::Different
+            }
+        },
+    };
+
+    CompareNode {
+        name,
+        relative_path: encode_path(&relative),
+        status,
+        left: left.map(|(_, m)| entry_side(&m)),
+        right: right.map(|(_, m)| entry_side(&m)),
+        error,
+        children,
+    }
+}
+
+fn entry_side(metadata: &fs::Metadata) -> EntrySide {
+    EntrySide {
+        kind: FileKind::from_metadata(metadata),
+        size: if metadata.is_file() {
+            metadata.len()
+        } else {
+            0
+        },
+        modified: metadata.modified().ok().map(epoch_seconds),
+    }
+}
+
+fn same_content(
+    (left, left_meta): &(PathBuf, fs::Metadata),
+    (right, right_meta): &(PathBuf, fs::Metadata),
+    compare_content: bool,
+) -> Result<bool, String> {
+    if FileKind::from_metadata(left_meta) != FileKind::from_metadata(right_meta) {
+        return Ok(false);
+    }
+    if left_meta.is_symlink() {
+        return Ok(fs::read_link(left).ok() == fs::read_link(right).ok());
+    }
+    if left_meta.len() != right_meta.len() {
+        return Ok(false);
+    }
+
+    if compare_content {
+        let hash = |path: &Path| {
+            digest_file(path, &[ChecksumAlgorithm::Blake3], |_| Ok(()))
+                .map(|mut digests| digests.remove(&ChecksumAlgorithm::Blake3))
+        };
+        return Ok(hash(left)? == hash(right)?);
+    }
+
+    let modified = |metadata: &fs::Metadata| metadata.modified().ok().map(epoch_seconds);
+    Ok(match (modified(left_meta), modified(right_meta)) {
+        (Some(l), Some(r)) => (l - r).abs() <= MTIME_TOLERANCE_SECS,
+        _ => false,
+    })
+}
+
+/// Returns the only-left, only-right, identical and different counts of a subtree
+fn count_entries(node: &CompareNode) -> [usize; 4] {
+    let is_dir =
+        |side: &Option<EntrySide>| side.as_ref().is_some_and(|s| s.kind == FileKind::Directory);
+    let mut counts = [0; 4];
+    if !is_dir(&node.left) && !is_dir(&node.right) {
+        counts[node.status as usize] += 1;
+    }
+    for child in &node.children {
+        for (total, count) in counts.iter_mut().zip(count_entries(child)) {
+            *total += count;
+        }
+    }
+    counts
+}
+
+// ------------------- Sync -------------------
+
+fn plan_node(node: &CompareNode, delete_extra: bool, actions: &mut Vec<SyncAction>) {
+    let kind = |side: &Option<EntrySide>| side.as_ref().map(|s| s.kind);
+    let action = |operation, bytes| SyncAction {
+        operation,
+        relative_path: node.relative_path.clone(),
+        bytes,
+    };
+    let left_size = node.left.as_ref().map(|s| s.size).unwrap_or(0);
+
+    match node.status {
+        CompareStatus::Identical => {}
+        CompareStatus::OnlyRight => {
+            if delete_extra {
+                actions.push(action(SyncOperation::Delete, 0));
+            }
+        }
+        CompareStatus::OnlyLeft | CompareStatus::Different => {
+            let (left, right) = (kind(&node.left), kind(&node.right));
+            if left == Some(FileKind::Directory) {
+                match right {
+                    Some(FileKind::Directory) => {}
+                    // A file standing where the folder goes has to make room
+                    Some(_) => {
+                        actions.push(action(SyncOperation::Delete, 0));
+                        actions.push(action(SyncOperation::CreateDirectory, 0));
+                    }
+                    None => actions.push(action(SyncOperation::CreateDirectory, 0)),
+                }
+                for child in &node.children {
+                    plan_node(child, delete_extra, actions);
+                }
+            } else if left.is_some() {
+                if right == Some(FileKind::Directory) {
+                    actions.push(action(SyncOperation::Delete, 0));
+                    actions.push(action(SyncOperation::Copy, left_size));
+                } else if right.is_some() {
+                    actions.push(action(SyncOperation::Update, left_size));
+                } else {
+                    actions.push(action(SyncOperation::Copy, left_size));
+                }
+            }
+        }
+    }
+}
+
+fn run_sync(job: &JobHandle, plan: &SyncPlan) -> Result<(), String> {
+    let source = decode_path(&plan.source);
+    let destination = decode_path(&plan.destination);
+    let mut processed = 0u64;
+
+    for action in &plan.actions {
+        job.check_cancelled()?;
+        let relative = decode_path(&action.relative_path);
+        let from = source.join(&relative);
+        let to = destination.join(&relative);
+
+        match action.operation {
+            SyncOperation::CreateDirectory => fs::create_dir_all(&to)
+                .map_err(|e| format!("Failed to create directory {:?}: {:?}", to, e))?,
+            SyncOperation::Copy | SyncOperation::Update => {
+                copy_entry(job, &from, &to, &mut processed, plan.total_bytes)?
+            }
+            SyncOperation::Delete => {
+                let result = match fs::symlink_metadata(&to) {
+                    Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(&to),
+                    Ok(_) => fs::remove_file(&to),
+                    Err(_) => Ok(()),
+                };
+                result.map_err(|e| format!("Failed to delete {:?}: {:?}", to, e))?;
+            }
+        }
+    }
+
+    job.progress(plan.total_bytes, plan.total_bytes, None);
+    Ok(())
+}
+
+/// Copies a file or symlink, replacing the target atomically.
+/// Permissions and modification time are kept so the next comparison sees identical files.
+fn copy_entry(
+    job: &JobHandle,
+    from: &Path,
+    to: &Path,
+    processed: &mut u64,
+    total: u64,
+) -> Result<(), String> {
+    let metadata = fs::symlink_metadata(from)
+        .map_err(|e| format!("Failed to read metadata of {:?}: {:?}", from, e))?;
+    let file_name = to
+        .file_name()
+        .ok_or(format!("Invalid destination: {:?}", to))?;
+    let mut temp_name = OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(format!(".{}.sync", std::process::id()));
+    let temp = to.with_file_name(temp_name);
+
+    let result = if metadata.is_symlink() {
+        copy_symlink(from, &temp)
+    } else {
+        (|| {
+            let mut input =
+                File::open(from).map_err(|e| format!("Failed to open {:?}: {:?}", from, e))?;
+            let mut output =
+                File::create(&temp).map_err(|e| format!("Failed to create {:?}: {:?}", to, e))?;
+            copy_with_progress(job, &mut input, &mut output, processed, total, from)?;
+            drop(output);
+
+            fs::set_permissions(&temp, metadata.permissions())
+                .map_err(|e| format!("Failed to set permissions of {:?}: {:?}", to, e))?;
+            if let Ok(modified) = metadata.modified() {
+                let _ = filetime::set_file_mtime(&temp, filetime::FileTime::from(modified));
+            }
+            Ok(())
+        })()
+    };
+
+    match result.and_then(|()| {
+        fs::rename(&temp, to).map_err(|e| format!("Failed to replace {:?}: {:?}", to, e))
+    }) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            let _ = fs::remove_file(&temp);
+            Err(e)
+        }
+    }
+}
+
+#[cfg(unix)]
+fn copy_symlink(from: &Path, to: &Path) -> Result<(), String> {
+    let target =
+        fs::read_link(from).map_err(|e| format!("Failed to read link {:?}: {:?}", from, e))?;
+    std::os::unix::fs::symlink(target, to)
+        .map_err(|e| format!("Failed to create link {:?}: {:?}", to, e))
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(from: &Path, _to: &Path) -> Result<(), String> {
+    Err(format!(
+        "Copying symbolic links is not supported on this platform: {:?}",
+        from
+    ))
+}
+
+fn check_relative_path(relative: &str) -> Result<(), String> {
+    let path = decode_path(relative);
+    let is_plain = path.components().next().is_some()
+        && path.components().all(|c| matches!(c, Component::Normal(_)));
+    if is_plain {
+        Ok(())
+    } else {
+        Err(format!(
+            "Refusing sync action outside the folders: {:?}",
+            relative
+        ))
+    }
+}
@@ -0,0 +1,117 @@
+use once_cell::sync::Lazy;
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+use tauri::{command, AppHandle, Emitter, Manager};
+
+use crate::error::ExplorerError;
+
+/// Event emitted with a `ConfigProblem` as soon as it happens
+pub const CONFIG_PROBLEM_EVENT: &str = "config-problem";
+
+// Every problem since startup, most happen while loading, before the frontend listens
+static PROBLEMS: Lazy<Mutex<Vec<ConfigProblem>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// A config file that couldn't be loaded or saved, or was only partly used
+#[derive(serde::Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ConfigProblem {
+    pub file: String, // e.g. "settings.json"
+    pub message: String,
+}
+
+/// Tells the user about a config problem: kept for `list_config_problems`, once even if
+/// it happens again, and emitted as a `config-problem` event
+pub fn report(app: &AppHandle, file: &str, message: String) {
+    let problem = ConfigProblem {
+        file: file.to_string(),
+        message,
+    };
+    let mut problems = PROBLEMS.lock().unwrap();
+    if !problems.contains(&problem) {
+        problems.push(problem.clone());
+    }
+    drop(problems);
+    let _ = app.emit(CONFIG_PROBLEM_EVENT, problem);
+}
+
+/// Config problems since startup, oldest first
+#[command]
+pub fn list_config_problems() -> Vec<ConfigProblem> {
+    PROBLEMS.lock().unwrap().clone()
+}
+
+/// Path of a config file in the app config dir, e.g. `~/.config/com.file-explorer.app/`
+pub fn config_path(app: &AppHandle, file_name: &str) -> Result<PathBuf, ExplorerError> {
+    let dir = app.path().app_config_dir().map_err(|e| {
+        ExplorerError::Unavailable(format!("Could not determine config directory: {}", e))
+    })?;
+    Ok(dir.join(file_name))
+}
+
+/// Reads a versioned JSON config file.
+/// Returns None if the file doesn't exist yet, and the stored `version` (0 if absent)
+/// with the document otherwise, so callers can migrate before deserializing.
+/// A file that isn't valid JSON is moved aside to `<name>.invalid` and treated as missing.
+pub fn read_versioned(path: &Path) -> Result<Option<(u32, serde_json::Value)>, ExplorerError> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(ExplorerError::io(e, path)),
+    };
+
+    match serde_json::from_slice::<serde_json::Value>(&bytes) {
+        Ok(document) => {
+            let version = document
+                .get("version")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(0) as u32;
+            Ok(Some((version, document)))
+        }
+        Err(_) => {
+            let mut invalid = path.as_os_str().to_owned();
+            invalid.push(".invalid");
+            fs::rename(path, &invalid).map_err(|e| ExplorerError::io(e, path))?;
+            Ok(None)
+        }
+    }
+}
+
+/// Fails when a file was written by a newer build, so it isn't overwritten with less data
+pub fn check_version(path: &Path, version: u32, supported: u32) -> Result<(), ExplorerError> {
+    if version > supported {
+        return Err(ExplorerError::Unavailable(format!(
+            "{} was written by a newer version (format {}, supported {})",
+            path.display(),
+            version,
+            supported
+        )));
+    }
+    Ok(())
+}
+
+/// Writes a config file atomically: a crash never leaves a truncated file behind
+pub fn write_json<T: serde::Serialize>(path: &Path, value: &T) -> Result<(), ExplorerError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| ExplorerError::io(e, dir))?;
+    }
+    let json = serde_json::to_vec_pretty(value)
+        .map_err(|e| ExplorerError::Internal(format!("Failed to serialize config: {}", e)))?;
+
+    let mut temp_name = path.as_os_str().to_owned();
+    temp_name.push(".tmp");
+    let temp = PathBuf::from(temp_name);
+    let result = fs::File::create(&temp)
+        .and_then(|mut file| {
+            file.write_all(&json)?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&temp, path));
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temp);
+        return Err(ExplorerError::io(e, path));
+    }
+    Ok(())
+}
@@ -0,0 +1,145 @@
+use std::fs;
+use tauri::command;
+
+use crate::filetype;
+use crate::format::Formatter;
+use crate::models::FileInfo;
+use crate::paths;
+
+/// Everything the properties panel shows about a single entry.
+/// Unix-only fields are None on other platforms.
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct FileDetails {
+    #[serde(flatten)]
+    pub info: FileInfo,
+    pub mode: Option<u32>,           // permission bits, including setuid/setgid/sticky
+    pub permissions: Option<String>, // e.g. "rwxr-xr-x"
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    pub owner: Option<String>,
+    pub group: Option<String>,
+    pub inode: Option<u64>,
+    pub device: Option<u64>,
+    pub hard_links: Option<u64>,
+    pub is_executable: bool,
+    pub is_readonly: bool,
+    pub symlink_target: Option<String>,
+}
+
+/// Returns detailed metadata of a file or folder.
+/// Symlinks are described themselves, with their target in `symlink_target`.
+#[command]
+pub fn get_file_details(path: String) -> Result<FileDetails, String> {
+    let file_path = &paths::decode_path(&path);
+    let metadata = fs::symlink_metadata(file_path)
+        .map_err(|e| format!("Failed to read metadata of {}: {:?}", path, e))?;
+
+    let file_name = file_path
+        .file_name()
+        .map(paths::display_name)
+        .unwrap_or_else(|| path.clone());
+
+    let mut info = FileInfo::from_metadata(file_name, path.clone(), &metadata);
+    if metadata.is_file() {
+        if let Some(detected) = filetype::detect(file_path) {
+            info.mime_type = Some(detected.mime_type);
+            info.category = Some(detected.category);
+        }
+    }
+
+    info.format(&Formatter::current());
+
+    let symlink_target = if metadata.is_symlink() {
+        fs::read_link(file_path)
+            .ok()
+            .map(|target| paths::encode_path(&target))
+    } else {
+        None
+    };
+
+    let mut details = FileDetails {
+        info,
+        mode: None,
+        permissions: None,
+        uid: None,
+        gid: None,
+        owner: None,
+        group: None,
+        inode: None,
+        device: None,
+        hard_links: None,
+        is_executable: false,
+        is_readonly: metadata.permissions().readonly(),
+        symlink_target,
+    };
+    fill_unix_details(&mut details, &metadata);
+
+    Ok(details)
+}
+
+/// Renders permission bits the way `ls -l` does, without the file type character
+pub fn format_mode(mode: u32) -> String {
+    let bit = |mask: u32, c: char| if mode & mask != 0 { c } else { '-' };
+    // Execute slot also carries setuid/setgid/sticky: lowercase if executable, uppercase if not
+    let special = |exec_mask: u32, special_mask: u32, set: char| {
+        match (mode & exec_mask != 0, mode & special_mask != 0) {
+            (true, true) => set,
+            (false, true) => set.to_ascii_uppercase(),
+            (true, false) => 'x',
+            (false, false) => '-',
+        }
+    };
+
+    [
+        bit(0o400, 'r'),
+        bit(0o200, 'w'),
+        special(0o100, 0o4000, 's'),
+        bit(0o040, 'r'),
+        bit(0o020, 'w'),
+        special(0o010, 0o2000, 's'),
+        bit(0o004, 'r'),
+        bit(0o002, 'w'),
+        special(0o001, 0o1000, 't'),
+    ]
+    .iter()
+    .collect()
+}
+
+#[cfg(unix)]
+fn fill_unix_details(details: &mut FileDetails, metadata: &fs::Metadata) {
+    use std::os::unix::fs::MetadataExt;
+
+    let mode = metadata.mode() & 0o7777;
+    details.mode = Some(mode);
+    details.permissions = Some(format_mode(mode));
+    details.uid = Some(metadata.uid());
+    details.gid = Some(metadata.gid());
+    details.owner = user_name(metadata.uid());
+    details.group = group_name(metadata.gid());
+    details.inode = Some(metadata.ino());
+    details.device = Some(metadata.dev());
+    details.hard_links = Some(metadata.nlink());
+    details.is_executable = metadata.is_file() && mode & 0o111 != 0;
+}
+
+#[cfg(not(unix))]
+fn fill_unix_details(details: &mut FileDetails, _metadata: &fs::Metadata) {
+    details.is_executable = details.info.category == Some(filetype::FileCategory::Executable);
+}
+
+/// Resolves a uid through NSS, so LDAP/sssd users are found as well
+#[cfg(unix)]
+pub fn user_name(uid: u32) -> Option<String> {
+    nix::unistd::User::from_uid(nix::unistd::Uid::from_raw(uid))
+        .ok()
+        .flatten()
+        .map(|user| user.name)
+}
+
+#[cfg(unix)]
+pub fn group_name(gid: u32) -> Option<String> {
+    nix::unistd::Group::from_gid(nix::unistd::Gid::from_raw(gid))
+        .ok()
+        .flatten()
+        .map(|group| group.name)
+}
@@ -1,10 +1,26 @@
-use chrono::{DateTime, Utc};
+use chrono::{Local, TimeZone};
 use dirs_next::{document_dir, home_dir, picture_dir};
-use std::{fs, path::Path, time::UNIX_EPOCH};
+use once_cell::sync::Lazy;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
 use tauri::command;
 use walkdir::WalkDir;
 
-use crate::models::FileInfo;
+use crate::error::{ExplorerError, SkipSummary};
+use crate::filetype::{self, FileCategory};
+use crate::format::Formatter;
+use crate::image_metadata;
+use crate::media;
+use crate::models::{FileInfo, FileKind};
+use crate::paths;
+use crate::settings;
 
 /// Helper function to detect file type from a path
 /// Returns a string describing the type: "file", "directory", "symlink", or "unknown"
@@ -62,22 +78,111 @@ pub fn get_file_type_info(path: &Path) -> (String, bool, bool, bool) {
     (file_type.to_string(), is_file, is_dir, is_symlink)
 }
 
-/// Determines file type from metadata using a match statement
-fn get_file_type_from_metadata(metadata: &fs::Metadata) -> String {
-    match (metadata.is_dir(), metadata.is_file(), metadata.is_symlink()) {
-        (true, _, _) => "directory".to_string(),
-        (_, true, _) => "file".to_string(),
-        (_, _, true) => "symlink".to_string(),
-        _ => "unknown".to_string(),
+// Cancellation flags of the listings still running, keyed by the request id the frontend chose
+static RUNNING_LISTINGS: Lazy<Mutex<HashMap<String, Arc<AtomicBool>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// A listing in progress. Reusing the id of a running request (e.g. one id per view)
+/// cancels the older one, so navigating away never leaves a stale walk behind.
+pub struct ListingRequest {
+    id: Option<String>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl ListingRequest {
+    pub fn register(id: Option<String>) -> Self {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        if let Some(id) = &id {
+            let previous = RUNNING_LISTINGS
+                .lock()
+                .unwrap()
+                .insert(id.clone(), cancelled.clone());
+            if let Some(previous) = previous {
+                previous.store(true, Ordering::Relaxed);
+            }
+        }
+        Self { id, cancelled }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub fn check_cancelled(&self) -> Result<(), ExplorerError> {
+        if self.is_cancelled() {
+            Err(ExplorerError::Cancelled)
+        } else {
+            Ok(())
+        }
     }
 }
 
+impl Drop for ListingRequest {
+    fn drop(&mut self) {
+        let Some(id) = &self.id else {
+            return;
+        };
+        let mut running = RUNNING_LISTINGS.lock().unwrap();
+        // A newer request may have taken over the id
+        if running
+            .get(id)
+            .is_some_and(|flag| Arc::ptr_eq(flag, &self.cancelled))
+        {
+            running.remove(id);
+        }
+    }
+}
+
+/// Cancels the listing started with `request_id`, which then fails with a `cancelled` error.
+/// Returns false if it already finished.
+#[command]
+pub fn cancel_listing(request_id: String) -> bool {
+    match RUNNING_LISTINGS.lock().unwrap().get(&request_id) {
+        Some(cancelled) => {
+            cancelled.store(true, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Builds the `FileInfo` of a listed entry.
+/// Directories get their recursive size, regular files get content-based type detection
+/// and, if `with_media` is set, audio/video metadata columns.
+/// The directory size walk stops early once `request` is cancelled.
+pub fn build_file_info(
+    file_name: String,
+    file_path: &Path,
+    metadata: &fs::Metadata,
+    with_media: bool,
+    request: Option<&ListingRequest>,
+) -> FileInfo {
+    let mut info = FileInfo::from_metadata(file_name, paths::encode_path(file_path), metadata);
+
+    if metadata.is_dir() {
+        info.file_size = calculate_directory_size(file_path, request);
+    } else if metadata.is_file() {
+        if let Some(detected) = filetype::detect(file_path) {
+            if with_media {
+                info.media = media::read_media_metadata_as(file_path, &detected);
+            }
+            info.mime_type = Some(detected.mime_type);
+            info.category = Some(detected.category);
+        }
+    }
+
+    info
+}
+
 /// Recursively calculate the total size of a directory
 /// Returns the sum of all file sizes within the directory and its subdirectories
-fn calculate_directory_size(dir_path: &Path) -> u64 {
+fn calculate_directory_size(dir_path: &Path, request: Option<&ListingRequest>) -> u64 {
     let mut total_size = 0u64;
     
     for entry in WalkDir::new(dir_path).into_iter().filter_map(|e| e.ok()) {
+        if request.is_some_and(|r| r.is_cancelled()) {
+            break;
+        }
         let path = entry.path();
         if path.is_file() {
             if let Ok(metadata) = fs::metadata(path) {
@@ -89,268 +194,318 @@ fn calculate_directory_size(dir_path: &Path) -> u64 {
     total_size
 }
 
-#[command]
-pub fn list_pictures() -> Result<Vec<FileInfo>, String> {
-    let pictures_dir = picture_dir().ok_or("Could not determine Pictures directory".to_string())?;
-
-    if !pictures_dir.exists() || !pictures_dir.is_dir() {
-        return Err(format!(
-            "Pictures folder not found or invalid: {:?}",
-            pictures_dir
-        ));
+/// Keeps only the entries of the requested category; directories never match a category
+fn filter_by_category(files: Vec<FileInfo>, category: Option<FileCategory>) -> Vec<FileInfo> {
+    match category {
+        Some(category) => files
+            .into_iter()
+            .filter(|f| f.category == Some(category))
+            .collect(),
+        None => files,
     }
+}
 
-    let mut files_info = Vec::new();
-    for entry in fs::read_dir(&pictures_dir)
-        .map_err(|e| format!("Failed to read Pictures folder: {:?}", e))?
-    {
-        if let Ok(entry) = entry {
-            let file_path = entry.path();
-            
-            // DETECTING FILE TYPES - Multiple methods available:
-            // Method 1: Using Path methods (simple, but may follow symlinks)
-            // let is_file = file_path.is_file();
-            // let is_dir = file_path.is_dir();
-            // let is_symlink = file_path.is_symlink();
-            
-            // Method 2: Using metadata (more accurate, checks actual file type)
-            if let Ok(metadata) = entry.metadata() {
-                // Check file type using metadata
-                // Uncomment these to use file type detection:
-                // let is_file = metadata.is_file();
-                // let is_dir = metadata.is_dir();
-                // let is_symlink = metadata.is_symlink();
-                
-                // Filter: Only process files (skip directories)
-                // Uncomment the next line to skip directories:
-                // if metadata.is_dir() { continue; }
-                
-                // Filter: Only process directories (skip files)
-                // Uncomment the next line to skip files:
-                // if metadata.is_file() { continue; }
-                
-                let file_name = file_path
-                    .file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or("")
-                    .to_string();
-                
-                // Determine file type from metadata using match
-                let is_directory = metadata.is_dir();
-                let file_type = get_file_type_from_metadata(&metadata);
-                
-                // Calculate size: for directories, recursively sum all files; for files, use metadata.len()
-                let file_size = if is_directory {
-                    calculate_directory_size(&file_path)
-                } else {
-                    metadata.len()
-                };
-                
-                let modification_date = metadata
-                    .modified()
-                    .ok()
-                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
-                    .and_then(|d| DateTime::<Utc>::from_timestamp(d.as_secs() as i64, 0))
-                    .map(|dt| dt.to_rfc3339())
-                    .unwrap_or_else(|| "Unknown".to_string());
-
-                let lower_name = file_name.to_lowercase();
-
-                files_info.push(FileInfo {
-                    file_name,
-                    file_size,
-                    modification_date,
-                    formatted_size: FileInfo::format_size(file_size),
-                    file_path: file_path.display().to_string(),
-                    lower_name,
-                    file_type,
-                });
-            }
-        }
-    }
+/// Orders `list_pictures` results, newest first
+#[derive(serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PictureSort {
+    Modified,
+    CaptureDate, // EXIF DateTimeOriginal, falling back to the modification time
+}
+
+/// Pictures taken (or modified) on the same local day
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct PictureGroup {
+    pub date: String, // "YYYY-MM-DD", or "Unknown"
+    pub files: Vec<FileInfo>,
+}
+
+/// Pairs each picture with its sort timestamp and orders them newest first
+fn sort_pictures(files: Vec<FileInfo>, sort_by: PictureSort) -> Vec<(Option<i64>, FileInfo)> {
+    let mut dated: Vec<_> = files
+        .into_iter()
+        .map(|file| {
+            let time = match sort_by {
+                PictureSort::Modified => file.modified,
+                PictureSort::CaptureDate => {
+                    image_metadata::capture_time(&paths::decode_path(&file.file_path)).or(file.modified)
+                }
+            };
+            (time, file)
+        })
+        .collect();
 
-    Ok(files_info)
+    dated.sort_by_key(|(time, _)| std::cmp::Reverse(*time));
+    dated
 }
 
+/// Lists pictures grouped by day, most recent day first. Folders are left out.
 #[command]
-pub fn list_downloads() -> Result<Vec<FileInfo>, String> {
-    let downloads_dir = home_dir()
-        .ok_or("Could not determine home directory".to_string())?
-        .join("Downloads");
+pub async fn list_pictures_by_date(
+    sort_by: Option<PictureSort>,
+    request_id: Option<String>,
+) -> Result<Vec<PictureGroup>, ExplorerError> {
+    let pictures_dir = pictures_dir()?;
+    let request = ListingRequest::register(request_id);
+    tokio::task::spawn_blocking(move || {
+        let files: Vec<FileInfo> = list_folder(&pictures_dir, false, &request)?
+            .files
+            .into_iter()
+            .filter(|f| f.kind != FileKind::Directory)
+            .collect();
+        let sorted = sort_pictures(files, sort_by.unwrap_or(PictureSort::CaptureDate));
+        request.check_cancelled()?;
+        Ok(group_by_date(sorted))
+    })
+    .await?
+}
 
-    if !downloads_dir.exists() || !downloads_dir.is_dir() {
-        return Err(format!(
-            "Downloads folder not found or invalid: {:?}",
-            downloads_dir
-        ));
-    }
+fn group_by_date(sorted: Vec<(Option<i64>, FileInfo)>) -> Vec<PictureGroup> {
+    let mut groups: Vec<PictureGroup> = Vec::new();
+    for (time, file) in sorted {
+        let date = time
+            .and_then(|secs| Local.timestamp_opt(secs, 0).single())
+            .map(|dt| dt.format("%Y-%m-%d").to_string())
+            .unwrap_or_else(|| "Unknown".to_string());
 
-    let mut files_info = Vec::new();
-    for entry in fs::read_dir(&downloads_dir)
-        .map_err(|e| format!("Failed to read Downloads folder: {:?}", e))?
-    {
-        if let Ok(entry) = entry {
-            let file_path = entry.path();
-            let file_name = file_path
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("")
-                .to_string();
-            if let Ok(metadata) = entry.metadata() {
-                // Determine file type from metadata using match
-                let is_directory = metadata.is_dir();
-                let file_type = get_file_type_from_metadata(&metadata);
-                
-                // Calculate size: for directories, recursively sum all files; for files, use metadata.len()
-                let file_size = if is_directory {
-                    calculate_directory_size(&file_path)
-                } else {
-                    metadata.len()
-                };
-                
-                let modification_date = metadata
-                    .modified()
-                    .ok()
-                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
-                    .and_then(|d| DateTime::<Utc>::from_timestamp(d.as_secs() as i64, 0))
-                    .map(|dt| dt.to_rfc3339())
-                    .unwrap_or_else(|| "Unknown".to_string());
-                let lower_name = file_name.clone().to_lowercase();
-                
-                files_info.push(FileInfo {
-                    file_name,
-                    file_size,
-                    modification_date,
-                    formatted_size: FileInfo::format_size(file_size),
-                    file_path: file_path.display().to_string(),
-                    lower_name,
-                    file_type,
-                });
-            }
+        match groups.last_mut() {
+            Some(group) if group.date == date => group.files.push(file),
+            _ => groups.push(PictureGroup {
+                date,
+                files: vec![file],
+            }),
         }
     }
 
-    Ok(files_info)
+    groups
 }
 
-#[command]
-pub fn list_documents() -> Result<Vec<FileInfo>, String> {
-    let documents_dir =
-        document_dir().ok_or("Could not determine Documents directory".to_string())?;
-
-    if !documents_dir.exists() || !documents_dir.is_dir() {
-        return Err(format!(
-            "Documents folder not found or invalid: {:?}",
-            documents_dir
-        ));
+/// Checks that `path` is an existing folder before listing it
+pub fn ensure_directory(path: &Path) -> Result<(), ExplorerError> {
+    let metadata = fs::metadata(path).map_err(|e| ExplorerError::io(e, path))?;
+    if !metadata.is_dir() {
+        return Err(ExplorerError::NotADirectory(paths::encode_path(path)));
     }
+    Ok(())
+}
 
+/// Entries of a folder, along with the ones that couldn't be listed
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct DirectoryListing {
+    pub files: Vec<FileInfo>,
+    pub skipped: SkipSummary,
+}
+
+/// Lists the direct children of a folder.
+/// Entries whose metadata can't be read are kept with their `error` set,
+/// entries that can't be shown at all are reported in `skipped`.
+fn list_folder(
+    dir: &Path,
+    with_media: bool,
+    request: &ListingRequest,
+) -> Result<DirectoryListing, ExplorerError> {
+    ensure_directory(dir)?;
+
+    let show_hidden = settings::current().show_hidden_files;
+    let formatter = Formatter::current();
     let mut files_info = Vec::new();
-    for entry in fs::read_dir(&documents_dir)
-        .map_err(|e| format!("Failed to read Documents folder: {:?}", e))?
-    {
-        if let Ok(entry) = entry {
-            let file_path = entry.path();
-            let file_name = file_path
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("")
-                .to_string();
-            if let Ok(metadata) = entry.metadata() {
-                // Determine file type from metadata using match
-                let is_directory = metadata.is_dir();
-                let file_type = get_file_type_from_metadata(&metadata);
-                
-                // Calculate size: for directories, recursively sum all files; for files, use metadata.len()
-                let file_size = if is_directory {
-                    calculate_directory_size(&file_path)
-                } else {
-                    metadata.len()
-                };
-                
-                let modification_date = metadata
-                    .modified()
-                    .ok()
-                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
-                    .and_then(|d| DateTime::<Utc>::from_timestamp(d.as_secs() as i64, 0))
-                    .map(|dt| dt.to_rfc3339())
-                    .unwrap_or_else(|| "Unknown".to_string());
-                let lower_name = file_name.to_lowercase();
-
-                files_info.push(FileInfo {
-                    file_name,
-                    file_size,
-                    modification_date,
-                    formatted_size: FileInfo::format_size(file_size),
-                    file_path: file_path.display().to_string(),
-                    lower_name,
-                    file_type,
-                });
+    let mut skipped = SkipSummary::default();
+    for entry in fs::read_dir(dir).map_err(|e| ExplorerError::io(e, dir))? {
+        request.check_cancelled()?;
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                // The folder itself stopped being readable, keep what was listed so far
+                skipped.add_io(dir, &e);
+                break;
             }
+        };
+        let metadata = entry.metadata();
+        if !show_hidden && paths::is_hidden(&entry.file_name(), metadata.as_ref().ok()) {
+            continue;
         }
+        let file_path = entry.path();
+        let file_name = paths::display_name(&entry.file_name());
+
+        let mut info = match metadata {
+            Ok(metadata) => {
+                build_file_info(file_name, &file_path, &metadata, with_media, Some(request))
+            }
+            Err(e) => FileInfo::unreadable(
+                file_name,
+                paths::encode_path(&file_path),
+                ExplorerError::io(e, &file_path),
+            ),
+        };
+        info.format(&formatter);
+        files_info.push(info);
     }
+    // A cancelled size walk leaves a partial total behind
+    request.check_cancelled()?;
+
+    Ok(DirectoryListing {
+        files: files_info,
+        skipped,
+    })
+}
+
+fn pictures_dir() -> Result<PathBuf, ExplorerError> {
+    picture_dir().ok_or(ExplorerError::Unavailable(
+        "Could not determine Pictures directory".to_string(),
+    ))
+}
+
+/// Lists the Pictures folder. Like the other listings it runs off the command thread,
+/// `request_id` allows cancelling it through `cancel_listing`.
+#[command]
+pub async fn list_pictures(
+    category: Option<FileCategory>,
+    sort_by: Option<PictureSort>,
+    with_media: Option<bool>,
+    request_id: Option<String>,
+) -> Result<DirectoryListing, ExplorerError> {
+    let pictures_dir = pictures_dir()?;
+    let request = ListingRequest::register(request_id);
+    tokio::task::spawn_blocking(move || {
+        let mut listing = list_folder(&pictures_dir, with_media.unwrap_or(false), &request)?;
+
+        let files_info = filter_by_category(listing.files, category);
+        listing.files = match sort_by {
+            Some(sort_by) => sort_pictures(files_info, sort_by)
+                .into_iter()
+                .map(|(_, file)| file)
+                .collect(),
+            None => files_info,
+        };
+        request.check_cancelled()?;
+        Ok(listing)
+    })
+    .await?
+}
+
+#[command]
+pub async fn list_downloads(
+    category: Option<FileCategory>,
+    with_media: Option<bool>,
+    request_id: Option<String>,
+) -> Result<DirectoryListing, ExplorerError> {
+    let downloads_dir = home_dir()
+        .ok_or(ExplorerError::Unavailable(
+            "Could not determine home directory".to_string(),
+        ))?
+        .join("Downloads");
+
+    list_folder_async(downloads_dir, category, with_media, request_id).await
+}
 
-    Ok(files_info)
+#[command]
+pub async fn list_documents(
+    category: Option<FileCategory>,
+    with_media: Option<bool>,
+    request_id: Option<String>,
+) -> Result<DirectoryListing, ExplorerError> {
+    let documents_dir = document_dir().ok_or(ExplorerError::Unavailable(
+        "Could not determine Documents directory".to_string(),
+    ))?;
+
+    list_folder_async(documents_dir, category, with_media, request_id).await
+}
+
+/// Runs `list_folder` on the blocking pool and keeps the entries of `category`
+async fn list_folder_async(
+    dir: PathBuf,
+    category: Option<FileCategory>,
+    with_media: Option<bool>,
+    request_id: Option<String>,
+) -> Result<DirectoryListing, ExplorerError> {
+    let request = ListingRequest::register(request_id);
+    tokio::task::spawn_blocking(move || {
+        let mut listing = list_folder(&dir, with_media.unwrap_or(false), &request)?;
+        listing.files = filter_by_category(listing.files, category);
+        Ok(listing)
+    })
+    .await?
 }
 
 /// Example command demonstrating how to detect files, folders, and other types
 /// This function lists all entries in a directory and categorizes them
-/// Returns: Vec of (name, type_string, is_file, is_dir, is_symlink)
+/// Returns: rows of (name, type_string, is_file, is_dir, is_symlink) and the skipped entries
 #[command]
-pub fn list_directory_contents(dir_path: String) -> Result<Vec<(String, String, bool, bool, bool)>, String> {
-    let path = Path::new(&dir_path);
-    
-    // Check if path exists
-    if !path.exists() {
-        return Err(format!("Path does not exist: {}", dir_path));
-    }
-    
-    // Check if it's a directory
-    if !path.is_dir() {
-        return Err(format!("Path is not a directory: {}", dir_path));
-    }
+pub async fn list_directory_contents(
+    dir_path: String,
+    request_id: Option<String>,
+) -> Result<DirectoryContents, ExplorerError> {
+    let path = paths::decode_path(&dir_path);
+    let request = ListingRequest::register(request_id);
+    tokio::task::spawn_blocking(move || read_directory_contents(&path, &request)).await?
+}
+
+/// (name, type_string, is_file, is_dir, is_symlink)
+pub type DirectoryEntryRow = (String, String, bool, bool, bool);
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct DirectoryContents {
+    pub entries: Vec<DirectoryEntryRow>,
+    pub skipped: SkipSummary,
+}
+
+fn read_directory_contents(
+    path: &Path,
+    request: &ListingRequest,
+) -> Result<DirectoryContents, ExplorerError> {
+    // Check that the path exists and is a directory
+    ensure_directory(path)?;
     
+    let show_hidden = settings::current().show_hidden_files;
     let mut results = Vec::new();
+    let mut skipped = SkipSummary::default();
     
-    for entry in fs::read_dir(path)
-        .map_err(|e| format!("Failed to read directory: {:?}", e))?
-    {
-        if let Ok(entry) = entry {
-            let file_path = entry.path();
-            let file_name = file_path
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("")
-                .to_string();
-            
-            // Multiple ways to detect file type:
-            
-            // Method 1: Using Path methods (may follow symlinks)
-            let _is_file_path = file_path.is_file();
-            let _is_dir_path = file_path.is_dir();
-            let _is_symlink_path = file_path.is_symlink();
-            
-            // Method 2: Using metadata (more reliable, checks actual type)
-            let (is_file_meta, is_dir_meta, is_symlink_meta) = if let Ok(metadata) = entry.metadata() {
-                (metadata.is_file(), metadata.is_dir(), metadata.is_symlink())
-            } else {
-                (false, false, false)
-            };
-            
-            // Method 3: Using our helper function
-            let file_type = detect_file_type(&file_path);
-            
-            // Store results: (name, type, is_file, is_dir, is_symlink)
-            results.push((
-                file_name,
-                file_type,
-                is_file_meta,
-                is_dir_meta,
-                is_symlink_meta,
-            ));
+    for entry in fs::read_dir(path).map_err(|e| ExplorerError::io(e, path))? {
+        request.check_cancelled()?;
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                // Same as `list_folder`: keep what was read before the folder failed
+                skipped.add_io(path, &e);
+                break;
+            }
+        };
+        let metadata = entry.metadata();
+        if !show_hidden && paths::is_hidden(&entry.file_name(), metadata.as_ref().ok()) {
+            continue;
         }
+        let file_path = entry.path();
+        let file_name = paths::display_name(&entry.file_name());
+        
+        // Multiple ways to detect file type:
+        
+        // Method 1: Using Path methods (may follow symlinks)
+        let _is_file_path = file_path.is_file();
+        let _is_dir_path = file_path.is_dir();
+        let _is_symlink_path = file_path.is_symlink();
+        
+        // Method 2: Using metadata (more reliable, checks actual type)
+        let (is_file_meta, is_dir_meta, is_symlink_meta) = if let Ok(metadata) = metadata {
+            (metadata.is_file(), metadata.is_dir(), metadata.is_symlink())
+        } else {
+            (false, false, false)
+        };
+        
+        // Method 3: Using our helper function
+        let file_type = detect_file_type(&file_path);
+        
+        // Store results: (name, type, is_file, is_dir, is_symlink)
+        results.push((
+            file_name,
+            file_type,
+            is_file_meta,
+            is_dir_meta,
+            is_symlink_meta,
+        ));
     }
     
-    Ok(results)
+    Ok(DirectoryContents {
+        entries: results,
+        skipped,
+    })
 }
-
@@ -1,19 +1,191 @@
+use once_cell::sync::Lazy;
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
 use sysinfo::Disks;
-use tauri::command;
+use tauri::{command, AppHandle, Emitter};
 
+use crate::error::ExplorerError;
+use crate::format::Formatter;
 use crate::models::DiskInfo;
 
+/// Event emitted with the `DiskInfo` of a newly mounted drive
+pub const DISK_MOUNTED_EVENT: &str = "disk-mounted";
+/// Event emitted with the last known `DiskInfo` of a drive that went away
+pub const DISK_UNMOUNTED_EVENT: &str = "disk-unmounted";
+/// Event emitted when a drive crosses the low space threshold, in either direction,
+/// and when a drive is already low on space when it is first seen
+pub const DISK_LOW_SPACE_EVENT: &str = "disk-low-space";
+
+/// How often free space is re-checked when no mount happened
+const CAPACITY_INTERVAL: Duration = Duration::from_secs(5);
+const DEFAULT_LOW_SPACE_PERCENT: f64 = 10.0;
+
+static MONITOR: Lazy<Mutex<Option<DiskMonitor>>> = Lazy::new(|| Mutex::new(None));
+
+struct DiskMonitor {
+    stop: Arc<AtomicBool>,
+    low_space_percent: Arc<Mutex<f64>>,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct LowSpaceEvent {
+    pub disk: DiskInfo,
+    pub low: bool, // false once space has been freed again
+}
+
 #[command]
 pub fn list_disks() -> Vec<DiskInfo> {
     let disks = Disks::new_with_refreshed_list();
+    let formatter = Formatter::current();
     let mut disk_info = Vec::new();
-    
+
     // Collect ALL disks - don't filter any out
     // This ensures all drives (C:, D:, etc.) are included
     for disk in disks.list() {
-        disk_info.push(DiskInfo::new(disk));
+        disk_info.push(DiskInfo::new(disk, &formatter));
     }
-    
+
     disk_info
 }
 
+/// Starts emitting `disk-mounted`, `disk-unmounted` and `disk-low-space` events and
+/// returns the current disks, so the frontend doesn't need to poll `list_disks`.
+/// A drive is low on space below `low_space_percent` (10 by default) of free space.
+/// Calling it again only updates the threshold.
+#[command]
+pub fn watch_disks(
+    app: AppHandle,
+    low_space_percent: Option<f64>,
+) -> Result<Vec<DiskInfo>, ExplorerError> {
+    let percent = low_space_percent
+        .unwrap_or(DEFAULT_LOW_SPACE_PERCENT)
+        .clamp(0.0, 100.0);
+
+    let mut monitor = MONITOR.lock().unwrap();
+    match monitor.as_ref() {
+        Some(running) => *running.low_space_percent.lock().unwrap() = percent,
+        None => {
+            let stop = Arc::new(AtomicBool::new(false));
+            let threshold = Arc::new(Mutex::new(percent));
+            let (thread_stop, thread_threshold) = (stop.clone(), threshold.clone());
+            thread::Builder::new()
+                .name("disk-monitor".to_string())
+                .spawn(move || monitor_disks(app, thread_stop, thread_threshold))
+                .map_err(|e| {
+                    ExplorerError::Internal(format!("Failed to start disk monitor: {:?}", e))
+                })?;
+            *monitor = Some(DiskMonitor {
+                stop,
+                low_space_percent: threshold,
+            });
+        }
+    }
+
+    Ok(list_disks())
+}
+
+/// Stops the monitor started by `watch_disks`
+#[command]
+pub fn unwatch_disks() {
+    if let Some(monitor) = MONITOR.lock().unwrap().take() {
+        monitor.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+fn monitor_disks(app: AppHandle, stop: Arc<AtomicBool>, low_space_percent: Arc<Mutex<f64>>) {
+    let mut mount_table = MountTable::open();
+    let mut known: HashMap<PathBuf, (DiskInfo, bool)> = HashMap::new();
+    let mut first_pass = true;
+
+    while !stop.load(Ordering::Relaxed) {
+        let disks = Disks::new_with_refreshed_list();
+        let threshold = *low_space_percent.lock().unwrap();
+        let formatter = Formatter::current();
+        let mut current: HashMap<PathBuf, (DiskInfo, bool)> = HashMap::new();
+
+        for disk in disks.list() {
+            let info = DiskInfo::new(disk, &formatter);
+            let mount_point = disk.mount_point().to_path_buf();
+            let low = is_low_on_space(disk, threshold);
+
+            // A drive that is already low when it shows up gets a low space event
+            // after its mount event, as if it had just crossed the threshold
+            let was_low = known.get(&mount_point).map(|(_, was_low)| *was_low);
+            if was_low.is_none() && !first_pass {
+                let _ = app.emit(DISK_MOUNTED_EVENT, info.clone());
+            }
+            if was_low.unwrap_or(false) != low {
+                let _ = app.emit(
+                    DISK_LOW_SPACE_EVENT,
+                    LowSpaceEvent {
+                        disk: info.clone(),
+                        low,
+                    },
+                );
+            }
+            current.insert(mount_point, (info, low));
+        }
+
+        for (mount_point, (info, _)) in known {
+            if !current.contains_key(&mount_point) {
+                let _ = app.emit(DISK_UNMOUNTED_EVENT, info);
+            }
+        }
+        known = current;
+        first_pass = false;
+
+        mount_table.wait_for_change(CAPACITY_INTERVAL);
+    }
+}
+
+/// Pseudo filesystems report no capacity and are never low on space
+fn is_low_on_space(disk: &sysinfo::Disk, percent: f64) -> bool {
+    let total = disk.total_space();
+    total > 0 && (disk.available_space() as f64) < total as f64 * percent / 100.0
+}
+
+/// The kernel flags `/proc/self/mountinfo` with POLLPRI whenever the mount table changes,
+/// so mounts are noticed immediately without re-reading the table in a loop
+struct MountTable {
+    #[cfg(target_os = "linux")]
+    file: Option<std::fs::File>,
+}
+
+impl MountTable {
+    fn open() -> Self {
+        Self {
+            #[cfg(target_os = "linux")]
+            file: std::fs::File::open("/proc/self/mountinfo").ok(),
+        }
+    }
+
+    /// Blocks until the mount table changes or `timeout` elapses
+    #[cfg(target_os = "linux")]
+    fn wait_for_change(&mut self, timeout: Duration) {
+        use nix::poll::{poll, PollFd, PollFlags, PollTimeout};
+        use std::os::fd::AsFd;
+
+        let Some(file) = self.file.as_ref() else {
+            thread::sleep(timeout);
+            return;
+        };
+        let mut fds = [PollFd::new(file.as_fd(), PollFlags::POLLPRI)];
+        let timeout = PollTimeout::try_from(timeout).unwrap_or(PollTimeout::MAX);
+        if poll(&mut fds, timeout).is_err() {
+            thread::sleep(CAPACITY_INTERVAL);
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn wait_for_change(&mut self, timeout: Duration) {
+        thread::sleep(timeout);
+    }
+}
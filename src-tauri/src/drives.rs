@@ -0,0 +1,536 @@
+use tauri::command;
+
+use crate::error::ExplorerError;
+
+/// Environment variable with the address of a bus to reach udisks2 on instead of the
+/// system bus, e.g. the private bus of `tests/udisks_mock.rs`
+pub const BUS_ADDRESS_VARIABLE: &str = "FILE_EXPLORER_UDISKS_BUS";
+
+/// Environment variable with a file to read the mount table from instead of
+/// `/proc/self/mountinfo`, so mounts of a mock bus can be listed
+pub const MOUNT_TABLE_VARIABLE: &str = "FILE_EXPLORER_MOUNTINFO";
+
+/// A process keeping a mounted filesystem busy
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BusyProcess {
+    pub pid: u32,
+    pub name: String,
+}
+
+/// Mounts a block device (e.g. "/dev/sdb1") and returns its mount point.
+/// Uses udisks2, so no root privileges are needed, and falls back to `mount`,
+/// which only works for devices listed in fstab with the `user` option.
+#[command]
+pub async fn mount_drive(device: String) -> Result<String, ExplorerError> {
+    platform::mount(&device).await
+}
+
+/// Unmounts a filesystem given either its device or its mount point.
+/// If it is in use, fails with `ExplorerError::Busy` listing the processes holding it open.
+#[command]
+pub async fn unmount_drive(target: String) -> Result<(), ExplorerError> {
+    platform::unmount(&target).await
+}
+
+/// Unmounts every filesystem of the drive holding `device` and powers it off,
+/// so it can be unplugged safely. Optical drives are ejected instead.
+/// Fails with `ExplorerError::InvalidArgument` for drives that aren't removable.
+#[command]
+pub async fn eject_drive(device: String) -> Result<(), ExplorerError> {
+    platform::eject(&device).await
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::{
+        collections::HashMap,
+        fs,
+        path::{Path, PathBuf},
+    };
+    use zbus::{
+        zvariant::{OwnedObjectPath, Value},
+        Connection, Proxy,
+    };
+
+    use super::{BusyProcess, BUS_ADDRESS_VARIABLE, MOUNT_TABLE_VARIABLE};
+    use crate::error::ExplorerError;
+
+    const UDISKS_SERVICE: &str = "org.freedesktop.UDisks2";
+    const UDISKS_MANAGER_PATH: &str = "/org/freedesktop/UDisks2/Manager";
+
+    /// A filesystem from `/proc/self/mountinfo`
+    struct MountEntry {
+        source: String,
+        mount_point: PathBuf,
+    }
+
+    enum UdisksError {
+        /// udisks2 isn't running or the bus can't be reached: use the command line tools
+        Unavailable,
+        /// udisks2 doesn't manage the device, e.g. NFS, FUSE and bind mounts:
+        /// the command line tools may still handle it
+        UnknownDevice,
+        Failed {
+            name: Option<String>,
+            message: String,
+        },
+    }
+
+    impl From<zbus::Error> for UdisksError {
+        fn from(error: zbus::Error) -> Self {
+            match error {
+                zbus::Error::MethodError(name, message, _) => {
+                    let name = name.as_str().to_string();
+                    if name == "org.freedesktop.DBus.Error.ServiceUnknown"
+                        || name == "org.freedesktop.DBus.Error.NameHasNoOwner"
+                    {
+                        Self::Unavailable
+                    } else {
+                        Self::Failed {
+                            message: message.unwrap_or_else(|| name.clone()),
+                            name: Some(name),
+                        }
+                    }
+                }
+                zbus::Error::InputOutput(_)
+                | zbus::Error::Address(_)
+                | zbus::Error::Handshake(_) => Self::Unavailable,
+                other => Self::Failed {
+                    name: None,
+                    message: other.to_string(),
+                },
+            }
+        }
+    }
+
+    impl UdisksError {
+        fn message(&self) -> String {
+            match self {
+                Self::Unavailable => "udisks2 is not available".to_string(),
+                Self::UnknownDevice => "udisks2 doesn't know the device".to_string(),
+                Self::Failed { message, .. } => message.clone(),
+            }
+        }
+    }
+
+    /// Error about `target`, a device or a mount point
+    fn failed(target: &str, message: String) -> ExplorerError {
+        ExplorerError::Io {
+            path: Some(target.to_string()),
+            message,
+        }
+    }
+
+    pub async fn mount(device: &str) -> Result<String, ExplorerError> {
+        if let Some(entry) = find_mount(device) {
+            return Ok(entry.mount_point.display().to_string());
+        }
+
+        match udisks_mount(device).await {
+            Ok(mount_point) => Ok(mount_point),
+            Err(UdisksError::Unavailable | UdisksError::UnknownDevice) => {
+                run_tool("mount", &[device])
+                    .await
+                    .map_err(|e| failed(device, e))?;
+                find_mount(device)
+                    .map(|entry| entry.mount_point.display().to_string())
+                    .ok_or(failed(device, "The device was not mounted".to_string()))
+            }
+            Err(UdisksError::Failed { message, .. }) => {
+                Err(failed(device, format!("Failed to mount: {}", message)))
+            }
+        }
+    }
+
+    pub async fn unmount(target: &str) -> Result<(), ExplorerError> {
+        let entry = find_mount(target).ok_or(ExplorerError::InvalidArgument(format!(
+            "{} is not mounted",
+            target
+        )))?;
+        if entry.mount_point == Path::new("/") {
+            return Err(ExplorerError::InvalidArgument(
+                "Refusing to unmount the root filesystem".to_string(),
+            ));
+        }
+
+        match udisks_unmount(&entry.source).await {
+            Ok(()) => return Ok(()),
+            Err(UdisksError::Unavailable | UdisksError::UnknownDevice) => {
+                let mount_point = entry.mount_point.to_string_lossy();
+                match run_tool("umount", &[&mount_point]).await {
+                    Err(e) if is_busy_message(&e) => {}
+                    result => return result.map_err(|e| failed(target, e)),
+                }
+            }
+            Err(UdisksError::Failed { name, message }) => {
+                let is_busy = name.as_deref() == Some("org.freedesktop.UDisks2.Error.DeviceBusy")
+                    || is_busy_message(&message);
+                if !is_busy {
+                    return Err(failed(target, format!("Failed to unmount: {}", message)));
+                }
+            }
+        }
+
+        // The filesystem is in use, tell who is holding it
+        let mount_point = entry.mount_point.clone();
+        let processes = tokio::task::spawn_blocking(move || busy_processes(&mount_point))
+            .await
+            .unwrap_or_default();
+        Err(ExplorerError::Busy {
+            path: entry.mount_point.display().to_string(),
+            processes,
+        })
+    }
+
+    pub async fn eject(device: &str) -> Result<(), ExplorerError> {
+        match UdisksDrive::of_device(device).await {
+            Ok(drive) => {
+                if !drive.is_removable().await {
+                    return Err(not_removable(device));
+                }
+                let mut partitions = Vec::new();
+                for entry in mounts() {
+                    if drive.holds(&entry.source).await {
+                        partitions.push(entry);
+                    }
+                }
+                unmount_all(&partitions).await?;
+                drive
+                    .power_off()
+                    .await
+                    .map_err(|e| failed(device, format!("Failed to eject: {}", e.message())))
+            }
+            Err(UdisksError::Unavailable | UdisksError::UnknownDevice) => {
+                let disk =
+                    parent_disk(device).ok_or(ExplorerError::NotFound(device.to_string()))?;
+                if !is_removable_disk(&disk) {
+                    return Err(not_removable(device));
+                }
+                let partitions: Vec<MountEntry> = mounts()
+                    .into_iter()
+                    .filter(|entry| parent_disk(&entry.source).as_deref() == Some(disk.as_str()))
+                    .collect();
+                unmount_all(&partitions).await?;
+                let disk_device = format!("/dev/{}", disk);
+                run_tool("eject", &[&disk_device])
+                    .await
+                    .map_err(|e| failed(&disk_device, e))
+            }
+            Err(UdisksError::Failed { message, .. }) => {
+                Err(failed(device, format!("Failed to eject: {}", message)))
+            }
+        }
+    }
+
+    fn not_removable(device: &str) -> ExplorerError {
+        ExplorerError::InvalidArgument(format!("{} is not on a removable drive", device))
+    }
+
+    /// Unmounts the partitions of a drive, checking all of them before the first is touched
+    async fn unmount_all(partitions: &[MountEntry]) -> Result<(), ExplorerError> {
+        if partitions
+            .iter()
+            .any(|entry| entry.mount_point == Path::new("/"))
+        {
+            return Err(ExplorerError::InvalidArgument(
+                "Refusing to eject the drive holding the root filesystem".to_string(),
+            ));
+        }
+        for entry in partitions {
+            unmount(&entry.mount_point.to_string_lossy()).await?;
+        }
+        Ok(())
+    }
+
+    /// `umount` output is only matched with `LC_ALL=C`, see `run_tool`
+    fn is_busy_message(message: &str) -> bool {
+        message.contains("target is busy") || message.contains("device is busy")
+    }
+
+    // ------------------- udisks2 -------------------
+
+    async fn connect() -> Result<Connection, UdisksError> {
+        let connection = match std::env::var(BUS_ADDRESS_VARIABLE) {
+            Ok(address) => {
+                zbus::connection::Builder::address(address.as_str())?
+                    .build()
+                    .await
+            }
+            Err(_) => Connection::system().await,
+        };
+        connection.map_err(|_| UdisksError::Unavailable)
+    }
+
+    async fn proxy(
+        connection: &Connection,
+        path: OwnedObjectPath,
+        interface: &'static str,
+    ) -> Result<Proxy<'static>, UdisksError> {
+        Ok(Proxy::new(connection, UDISKS_SERVICE, path, interface).await?)
+    }
+
+    /// Object path of the udisks2 block device for `/dev/...`
+    async fn resolve_device(
+        connection: &Connection,
+        device: &str,
+    ) -> Result<OwnedObjectPath, UdisksError> {
+        let manager = Proxy::new(
+            connection,
+            UDISKS_SERVICE,
+            UDISKS_MANAGER_PATH,
+            "org.freedesktop.UDisks2.Manager",
+        )
+        .await?;
+
+        let spec = HashMap::from([("path", Value::from(device))]);
+        let paths: Vec<OwnedObjectPath> =
+            manager.call("ResolveDevice", &(spec, no_options())).await?;
+        paths.into_iter().next().ok_or(UdisksError::UnknownDevice)
+    }
+
+    async fn udisks_mount(device: &str) -> Result<String, UdisksError> {
+        let connection = connect().await?;
+        let block = resolve_device(&connection, device).await?;
+        let filesystem = proxy(&connection, block, "org.freedesktop.UDisks2.Filesystem").await?;
+        Ok(filesystem.call("Mount", &(no_options(),)).await?)
+    }
+
+    async fn udisks_unmount(device: &str) -> Result<(), UdisksError> {
+        let connection = connect().await?;
+        let block = resolve_device(&connection, device).await?;
+        let filesystem = proxy(&connection, block, "org.freedesktop.UDisks2.Filesystem").await?;
+        Ok(filesystem.call("Unmount", &(no_options(),)).await?)
+    }
+
+    /// The udisks2 drive object behind a block device
+    struct UdisksDrive {
+        connection: Connection,
+        path: OwnedObjectPath,
+        drive: Proxy<'static>,
+    }
+
+    impl UdisksDrive {
+        async fn of_device(device: &str) -> Result<Self, UdisksError> {
+            let connection = connect().await?;
+            let path = drive_path(&connection, device)
+                .await?
+                .ok_or(UdisksError::Failed {
+                    name: None,
+                    message: format!("{} is not a physical drive", device),
+                })?;
+            let drive = proxy(&connection, path.clone(), "org.freedesktop.UDisks2.Drive").await?;
+            Ok(Self {
+                connection,
+                path,
+                drive,
+            })
+        }
+
+        /// USB sticks, card readers and optical drives, not internal disks
+        async fn is_removable(&self) -> bool {
+            for property in ["Removable", "Ejectable"] {
+                if self.drive.get_property::<bool>(property).await == Ok(true) {
+                    return true;
+                }
+            }
+            false
+        }
+
+        /// Whether the device, e.g. the source of a mount, is on this drive
+        async fn holds(&self, device: &str) -> bool {
+            device.starts_with("/dev/")
+                && drive_path(&self.connection, device)
+                    .await
+                    .is_ok_and(|path| path.as_ref() == Some(&self.path))
+        }
+
+        async fn power_off(&self) -> Result<(), UdisksError> {
+            let can_power_off: bool = self
+                .drive
+                .get_property("CanPowerOff")
+                .await
+                .unwrap_or(false);
+            let method = if can_power_off { "PowerOff" } else { "Eject" };
+            Ok(self.drive.call(method, &(no_options(),)).await?)
+        }
+    }
+
+    /// Drive object of a block device, None for devices without one, e.g. loop devices
+    async fn drive_path(
+        connection: &Connection,
+        device: &str,
+    ) -> Result<Option<OwnedObjectPath>, UdisksError> {
+        let block_path = resolve_device(connection, device).await?;
+        let block = proxy(connection, block_path, "org.freedesktop.UDisks2.Block").await?;
+        let drive_path: OwnedObjectPath = block.get_property("Drive").await?;
+        Ok(Some(drive_path).filter(|path| path.as_str() != "/"))
+    }
+
+    fn no_options() -> HashMap<&'static str, Value<'static>> {
+        HashMap::new()
+    }
+
+    // ------------------- Command line fallback -------------------
+
+    async fn run_tool(program: &str, args: &[&str]) -> Result<(), String> {
+        // Untranslated messages, so a busy filesystem can be recognized
+        let output = tokio::process::Command::new(program)
+            .args(args)
+            .env("LC_ALL", "C")
+            .output()
+            .await
+            .map_err(|e| format!("Failed to run {}: {:?}", program, e))?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(format!(
+                "{} failed: {}",
+                program,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))
+        }
+    }
+
+    // ------------------- Mount table -------------------
+
+    fn mounts() -> Vec<MountEntry> {
+        let table = std::env::var_os(MOUNT_TABLE_VARIABLE)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("/proc/self/mountinfo"));
+        let Ok(content) = fs::read_to_string(table) else {
+            return Vec::new();
+        };
+
+        // `<id> <parent> <major:minor> <root> <mount point> <options> [tags...] - <type> <source> ...`
+        content
+            .lines()
+            .filter_map(|line| {
+                let (left, right) = line.split_once(" - ")?;
+                let mount_point = left.split(' ').nth(4)?;
+                let source = right.split(' ').nth(1)?;
+                Some(MountEntry {
+                    source: unescape_octal(source),
+                    mount_point: PathBuf::from(unescape_octal(mount_point)),
+                })
+            })
+            .collect()
+    }
+
+    /// Finds a mounted filesystem by mount point or by device, resolving
+    /// `/dev/disk/by-*` links. The most recent mount wins, like the kernel does.
+    fn find_mount(target: &str) -> Option<MountEntry> {
+        let target_path = fs::canonicalize(target).unwrap_or_else(|_| PathBuf::from(target));
+        mounts().into_iter().rev().find(|entry| {
+            entry.mount_point == target_path
+                || entry.source == target
+                || fs::canonicalize(&entry.source).is_ok_and(|source| source == target_path)
+        })
+    }
+
+    /// The kernel escapes space, tab, newline and backslash as `\ooo`
+    fn unescape_octal(field: &str) -> String {
+        let bytes = field.as_bytes();
+        let mut decoded = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            let escaped = bytes
+                .get(i..i + 4)
+                .filter(|s| s[0] == b'\\')
+                .and_then(|s| std::str::from_utf8(&s[1..]).ok())
+                .and_then(|octal| u8::from_str_radix(octal, 8).ok());
+            match escaped {
+                Some(byte) => {
+                    decoded.push(byte);
+                    i += 4;
+                }
+                None => {
+                    decoded.push(bytes[i]);
+                    i += 1;
+                }
+            }
+        }
+        String::from_utf8_lossy(&decoded).into_owned()
+    }
+
+    /// Kernel name of the whole disk holding `device`, e.g. "sdb" for "/dev/sdb1"
+    fn parent_disk(device: &str) -> Option<String> {
+        let device = fs::canonicalize(device).ok()?;
+        let name = device.file_name()?.to_string_lossy().into_owned();
+        let sys_path = Path::new("/sys/class/block").join(&name);
+        if !sys_path.join("partition").exists() {
+            return sys_path.exists().then_some(name);
+        }
+        fs::canonicalize(&sys_path)
+            .ok()?
+            .parent()?
+            .file_name()
+            .map(|disk| disk.to_string_lossy().into_owned())
+    }
+
+    /// `/sys/block/<disk>/removable`, only used without udisks2
+    fn is_removable_disk(disk: &str) -> bool {
+        fs::read_to_string(Path::new("/sys/block").join(disk).join("removable"))
+            .is_ok_and(|removable| removable.trim() == "1")
+    }
+
+    // ------------------- Busy devices -------------------
+
+    /// Processes with their working directory, root or an open file on the filesystem.
+    /// Processes of other users can't be inspected without privileges and are missing.
+    fn busy_processes(mount_point: &Path) -> Vec<BusyProcess> {
+        let Ok(entries) = fs::read_dir("/proc") else {
+            return Vec::new();
+        };
+
+        let mut processes: Vec<BusyProcess> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+                let proc_dir = entry.path();
+
+                let uses = |link: PathBuf| {
+                    fs::read_link(link).is_ok_and(|target| target.starts_with(mount_point))
+                };
+                let holds_mount = ["cwd", "root", "exe"]
+                    .iter()
+                    .any(|link| uses(proc_dir.join(link)))
+                    || fs::read_dir(proc_dir.join("fd"))
+                        .map(|fds| fds.flatten().any(|fd| uses(fd.path())))
+                        .unwrap_or(false);
+                if !holds_mount {
+                    return None;
+                }
+
+                let name = fs::read_to_string(proc_dir.join("comm"))
+                    .map(|comm| comm.trim_end().to_string())
+                    .unwrap_or_default();
+                Some(BusyProcess { pid, name })
+            })
+            .collect();
+        processes.sort_by_key(|p| p.pid);
+        processes
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod platform {
+    use crate::error::ExplorerError;
+
+    pub async fn mount(_device: &str) -> Result<String, ExplorerError> {
+        Err(ExplorerError::Unavailable(
+            "Mounting drives is only supported on Linux".to_string(),
+        ))
+    }
+
+    pub async fn unmount(_target: &str) -> Result<(), ExplorerError> {
+        Err(ExplorerError::Unavailable(
+            "Unmounting drives is only supported on Linux".to_string(),
+        ))
+    }
+
+    pub async fn eject(_device: &str) -> Result<(), ExplorerError> {
+        Err(ExplorerError::Unavailable(
+            "Ejecting drives is only supported on Linux".to_string(),
+        ))
+    }
+}
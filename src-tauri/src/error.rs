@@ -0,0 +1,256 @@
+use std::{collections::BTreeMap, fmt, io, path::Path};
+
+use crate::drives::BusyProcess;
+use crate::paths::encode_path;
+
+/// Error returned by commands, serialized as `{ code, message, path }` so the frontend
+/// can react to the kind of failure instead of parsing messages.
+/// `Busy` also carries `processes`.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(into = "ExplorerErrorWire", from = "ExplorerErrorWire")]
+pub enum ExplorerError {
+    NotFound(String), // path
+    PermissionDenied(String),
+    NotADirectory(String),
+    AlreadyExists(String),
+    Io {
+        path: Option<String>,
+        message: String,
+    },
+    Busy {
+        path: String,                // mount point
+        processes: Vec<BusyProcess>, // empty when they couldn't be identified
+    },
+    Cancelled,
+    SessionClosed,        // a listing session that was closed or evicted
+    InvalidQuery(String), // why the query was rejected
+    InvalidName(String),  // why a new file name was rejected
+    InvalidArgument(String),
+    Unavailable(String), // e.g. a standard folder that isn't configured
+    Internal(String),    // failures that aren't about the filesystem, like a panicked task
+}
+
+impl ExplorerError {
+    /// Maps an I/O error on `path` to the matching variant
+    pub fn io(error: io::Error, path: &Path) -> Self {
+        let path = encode_path(path);
+        match error.kind() {
+            io::ErrorKind::NotFound => Self::NotFound(path),
+            io::ErrorKind::PermissionDenied => Self::PermissionDenied(path),
+            io::ErrorKind::NotADirectory => Self::NotADirectory(path),
+            io::ErrorKind::AlreadyExists => Self::AlreadyExists(path),
+            _ => Self::Io {
+                path: Some(path),
+                message: error.to_string(),
+            },
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NotFound(_) => "not_found",
+            Self::PermissionDenied(_) => "permission_denied",
+            Self::NotADirectory(_) => "not_a_directory",
+            Self::AlreadyExists(_) => "already_exists",
+            Self::Io { .. } => "io",
+            Self::Busy { .. } => "busy",
+            Self::Cancelled => "cancelled",
+            Self::SessionClosed => "session_closed",
+            Self::InvalidQuery(_) => "invalid_query",
+            Self::InvalidName(_) => "invalid_name",
+            Self::InvalidArgument(_) => "invalid_argument",
+            Self::Unavailable(_) => "unavailable",
+            Self::Internal(_) => "internal",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            Self::NotFound(_) => "No such file or directory".to_string(),
+            Self::PermissionDenied(_) => "Permission denied".to_string(),
+            Self::NotADirectory(_) => "Not a directory".to_string(),
+            Self::AlreadyExists(_) => "File exists".to_string(),
+            Self::Busy { processes, .. } if processes.is_empty() => "Device is busy".to_string(),
+            Self::Busy { processes, .. } => {
+                let users: Vec<String> = processes
+                    .iter()
+                    .map(|p| format!("{} (pid {})", p.name, p.pid))
+                    .collect();
+                format!("Device is used by {}", users.join(", "))
+            }
+            Self::Cancelled => "Cancelled".to_string(),
+            Self::SessionClosed => "Listing session is closed or expired".to_string(),
+            Self::Io { message, .. }
+            | Self::InvalidQuery(message)
+            | Self::InvalidName(message)
+            | Self::InvalidArgument(message)
+            | Self::Unavailable(message)
+            | Self::Internal(message) => message.clone(),
+        }
+    }
+
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            Self::NotFound(path)
+            | Self::PermissionDenied(path)
+            | Self::NotADirectory(path)
+            | Self::AlreadyExists(path)
+            | Self::Busy { path, .. } => Some(path),
+            Self::Io { path, .. } => path.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ExplorerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.path() {
+            Some(path) => write!(f, "{}: {}", self.message(), path),
+            None => write!(f, "{}", self.message()),
+        }
+    }
+}
+
+impl std::error::Error for ExplorerError {}
+
+impl From<io::Error> for ExplorerError {
+    fn from(error: io::Error) -> Self {
+        Self::Io {
+            path: None,
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<tokio::task::JoinError> for ExplorerError {
+    fn from(error: tokio::task::JoinError) -> Self {
+        if error.is_cancelled() {
+            Self::Cancelled
+        } else {
+            Self::Internal(format!("Task join error: {:?}", error))
+        }
+    }
+}
+
+/// Lets commands that still return `String` errors call into `ExplorerError` helpers with `?`
+impl From<ExplorerError> for String {
+    fn from(error: ExplorerError) -> Self {
+        error.to_string()
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ExplorerErrorWire {
+    code: String,
+    message: String,
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    processes: Vec<BusyProcess>,
+}
+
+impl From<ExplorerError> for ExplorerErrorWire {
+    fn from(error: ExplorerError) -> Self {
+        Self {
+            code: error.code().to_string(),
+            message: error.message(),
+            path: error.path().map(str::to_string),
+            processes: match error {
+                ExplorerError::Busy { processes, .. } => processes,
+                _ => Vec::new(),
+            },
+        }
+    }
+}
+
+impl From<ExplorerErrorWire> for ExplorerError {
+    fn from(wire: ExplorerErrorWire) -> Self {
+        let path = wire.path.unwrap_or_default();
+        match wire.code.as_str() {
+            "not_found" => Self::NotFound(path),
+            "permission_denied" => Self::PermissionDenied(path),
+            "not_a_directory" => Self::NotADirectory(path),
+            "already_exists" => Self::AlreadyExists(path),
+            "busy" => Self::Busy {
+                path,
+                processes: wire.processes,
+            },
+            "cancelled" => Self::Cancelled,
+            "session_closed" => Self::SessionClosed,
+            "invalid_query" => Self::InvalidQuery(wire.message),
+            "invalid_name" => Self::InvalidName(wire.message),
+            "invalid_argument" => Self::InvalidArgument(wire.message),
+            "unavailable" => Self::Unavailable(wire.message),
+            "internal" => Self::Internal(wire.message),
+            // "io" and codes from a newer build
+            _ => Self::Io {
+                path: Some(path).filter(|p| !p.is_empty()),
+                message: wire.message,
+            },
+        }
+    }
+}
+
+// ------------------- Skipped entries -------------------
+
+/// Skipped paths kept in a summary, the counts still cover every skipped entry
+pub const MAX_SKIPPED_PATHS: usize = 200;
+
+/// Why an entry is missing from a listing or from the index
+#[derive(serde::Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    PermissionDenied,
+    BrokenSymlink,
+    Io,
+}
+
+impl SkipReason {
+    pub fn from_io(error: &io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::PermissionDenied => Self::PermissionDenied,
+            _ => Self::Io,
+        }
+    }
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct SkippedEntry {
+    pub path: String,
+    pub reason: SkipReason,
+    pub message: String,
+}
+
+/// Entries left out of a listing or an index build, so the UI can tell why files are missing
+#[derive(serde::Serialize, Debug, Clone, Default)]
+pub struct SkipSummary {
+    pub total: usize,
+    pub counts: BTreeMap<SkipReason, usize>,
+    pub entries: Vec<SkippedEntry>, // the first MAX_SKIPPED_PATHS of them
+}
+
+impl SkipSummary {
+    pub fn add(&mut self, path: &Path, reason: SkipReason, message: impl Into<String>) {
+        self.total += 1;
+        *self.counts.entry(reason).or_default() += 1;
+        if self.entries.len() < MAX_SKIPPED_PATHS {
+            self.entries.push(SkippedEntry {
+                path: encode_path(path),
+                reason,
+                message: message.into(),
+            });
+        }
+    }
+
+    pub fn add_io(&mut self, path: &Path, error: &io::Error) {
+        self.add(path, SkipReason::from_io(error), error.to_string());
+    }
+
+    pub fn add_broken_symlink(&mut self, path: &Path) {
+        self.add(
+            path,
+            SkipReason::BrokenSymlink,
+            "Symlink target does not exist",
+        );
+    }
+}
@@ -0,0 +1,80 @@
+use std::{fs, io, path::Path};
+use tauri::command;
+
+use crate::error::ExplorerError;
+use crate::paths::{decode_path, encode_path};
+
+/// Renames a file or folder within its folder and returns the new path.
+/// Existing entries are never replaced, see `rename_no_replace`.
+#[command]
+pub async fn rename_file(path: String, new_name: String) -> Result<String, ExplorerError> {
+    tokio::task::spawn_blocking(move || {
+        check_file_name(&new_name)?;
+        let path = decode_path(&path);
+        let parent = path
+            .parent()
+            .filter(|parent| path.file_name().is_some() && !parent.as_os_str().is_empty())
+            .ok_or(ExplorerError::InvalidName(format!(
+                "Can't rename {}",
+                encode_path(&path)
+            )))?;
+        let target = parent.join(&new_name);
+
+        fs::symlink_metadata(&path).map_err(|e| ExplorerError::io(e, &path))?;
+        rename_no_replace(&path, &target).map_err(|e| match e.kind() {
+            io::ErrorKind::AlreadyExists => ExplorerError::AlreadyExists(encode_path(&target)),
+            _ => ExplorerError::io(e, &path),
+        })?;
+        Ok(encode_path(&target))
+    })
+    .await?
+}
+
+/// Permanently deletes a file, symlink or folder with everything inside it
+#[command]
+pub async fn delete_file(path: String) -> Result<(), ExplorerError> {
+    tokio::task::spawn_blocking(move || {
+        let path = decode_path(&path);
+        let metadata = fs::symlink_metadata(&path).map_err(|e| ExplorerError::io(e, &path))?;
+        let result = if metadata.is_dir() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        result.map_err(|e| ExplorerError::io(e, &path))
+    })
+    .await?
+}
+
+/// Renames `from` unless `to` exists. Atomic on Linux with glibc; elsewhere, and on
+/// filesystems without `RENAME_NOREPLACE`, an entry created between the check and
+/// the rename is still replaced.
+fn rename_no_replace(from: &Path, to: &Path) -> io::Result<()> {
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    {
+        use nix::{
+            errno::Errno,
+            fcntl::{renameat2, RenameFlags},
+        };
+        match renameat2(None, from, None, to, RenameFlags::RENAME_NOREPLACE) {
+            Err(Errno::EINVAL) => {} // not supported by the filesystem
+            result => return result.map_err(io::Error::from),
+        }
+    }
+    if fs::symlink_metadata(to).is_ok() {
+        return Err(io::ErrorKind::AlreadyExists.into());
+    }
+    fs::rename(from, to)
+}
+
+/// New names come from the user, so they are plain strings and a single component
+fn check_file_name(name: &str) -> Result<(), ExplorerError> {
+    let is_single_component = Path::new(name).file_name() == Some(name.as_ref());
+    if name.is_empty() || name.contains('\0') || !is_single_component {
+        return Err(ExplorerError::InvalidName(format!(
+            "Invalid file name: {:?}",
+            name
+        )));
+    }
+    Ok(())
+}
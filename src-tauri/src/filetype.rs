@@ -0,0 +1,317 @@
+use std::{fs::File, io::Read, path::Path};
+
+/// Number of leading bytes read when sniffing a file's content.
+/// Large enough to reach the `ustar` marker of tar archives at offset 257.
+const SNIFF_LEN: usize = 512;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum FileCategory {
+    Image,
+    Video,
+    Audio,
+    Document,
+    Archive,
+    Code,
+    Executable,
+    Other,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DetectedType {
+    pub mime_type: String,
+    pub category: FileCategory,
+}
+
+impl DetectedType {
+    fn new(mime_type: &str, category: FileCategory) -> Self {
+        Self {
+            mime_type: mime_type.to_string(),
+            category,
+        }
+    }
+}
+
+/// Detects the type of a file from its first bytes, falling back to the extension.
+/// Returns None if the file can't be read.
+pub fn detect(path: &Path) -> Option<DetectedType> {
+    let mut file = File::open(path).ok()?;
+    let mut header = Vec::with_capacity(SNIFF_LEN);
+    file.by_ref()
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut header)
+        .ok()?;
+
+    Some(detect_from_bytes(&header, extension(path).as_deref()))
+}
+
+/// Detects the type of a file from its extension only, without touching the disk.
+/// Used where opening every file would be too slow, e.g. listing pages.
+pub fn detect_by_extension(path: &Path) -> Option<DetectedType> {
+    let ext = extension(path)?;
+    from_extension(&ext).map(|(mime, category)| DetectedType::new(mime, category))
+}
+
+/// Detects the type from the extension, and only reads the file when the extension is
+/// missing or unknown. Same result as `detect` unless the extension contradicts the
+/// content, e.g. a PNG named `.txt`.
+pub fn detect_fast(path: &Path) -> Option<DetectedType> {
+    detect_by_extension(path).or_else(|| detect(path))
+}
+
+/// Combines magic byte sniffing with the extension.
+/// Container formats (zip, OLE) are refined using the extension, e.g. `.docx` is a zip file.
+pub fn detect_from_bytes(header: &[u8], ext: Option<&str>) -> DetectedType {
+    let by_extension = ext.and_then(from_extension);
+
+    if let Some((mime, category)) = sniff(header) {
+        let is_container = mime == "application/zip" || mime == "application/x-ole-storage";
+        if let (true, Some((ext_mime, ext_category))) = (is_container, by_extension) {
+            return DetectedType::new(ext_mime, ext_category);
+        }
+        return DetectedType::new(mime, category);
+    }
+
+    if let Some((mime, category)) = by_extension {
+        return DetectedType::new(mime, category);
+    }
+
+    if looks_like_text(header) {
+        DetectedType::new("text/plain", FileCategory::Document)
+    } else {
+        DetectedType::new("application/octet-stream", FileCategory::Other)
+    }
+}
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+}
+
+/// Matches well-known magic numbers at the start of the file
+fn sniff(header: &[u8]) -> Option<(&'static str, FileCategory)> {
+    use FileCategory::*;
+
+    let starts = |magic: &[u8]| header.starts_with(magic);
+    let at = |offset: usize, magic: &[u8]| {
+        header.len() >= offset + magic.len() && &header[offset..offset + magic.len()] == magic
+    };
+
+    let detected = if starts(b"\x89PNG\r\n\x1a\n") {
+        ("image/png", Image)
+    } else if starts(b"\xff\xd8\xff") {
+        ("image/jpeg", Image)
+    } else if starts(b"GIF87a") || starts(b"GIF89a") {
+        ("image/gif", Image)
+    } else if starts(b"RIFF") && at(8, b"WEBP") {
+        ("image/webp", Image)
+    } else if starts(b"BM") && header.len() >= 14 {
+        ("image/bmp", Image)
+    } else if starts(b"II*\0") || starts(b"MM\0*") {
+        ("image/tiff", Image)
+    } else if starts(b"\0\0\x01\0") {
+        ("image/x-icon", Image)
+    } else if at(4, b"ftyp") {
+        return sniff_iso_media(header);
+    } else if starts(b"\x1a\x45\xdf\xa3") {
+        if header.windows(4).any(|w| w == b"webm") {
+            ("video/webm", Video)
+        } else {
+            ("video/x-matroska", Video)
+        }
+    } else if starts(b"RIFF") && at(8, b"AVI ") {
+        ("video/x-msvideo", Video)
+    } else if starts(b"RIFF") && at(8, b"WAVE") {
+        ("audio/wav", Audio)
+    } else if starts(b"ID3") || starts(b"\xff\xfb") || starts(b"\xff\xf3") || starts(b"\xff\xf2") {
+        ("audio/mpeg", Audio)
+    } else if starts(b"fLaC") {
+        ("audio/flac", Audio)
+    } else if starts(b"OggS") {
+        ("audio/ogg", Audio)
+    } else if starts(b"%PDF-") {
+        ("application/pdf", Document)
+    } else if starts(b"{\\rtf") {
+        ("application/rtf", Document)
+    } else if starts(b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1") {
+        ("application/x-ole-storage", Document)
+    } else if starts(b"PK\x03\x04") || starts(b"PK\x05\x06") {
+        ("application/zip", Archive)
+    } else if starts(b"\x1f\x8b") {
+        ("application/gzip", Archive)
+    } else if starts(b"\x28\xb5\x2f\xfd") {
+        ("application/zstd", Archive)
+    } else if starts(b"BZh") {
+        ("application/x-bzip2", Archive)
+    } else if starts(b"\xfd7zXZ\0") {
+        ("application/x-xz", Archive)
+    } else if starts(b"7z\xbc\xaf\x27\x1c") {
+        ("application/x-7z-compressed", Archive)
+    } else if starts(b"Rar!\x1a\x07") {
+        ("application/vnd.rar", Archive)
+    } else if at(257, b"ustar") {
+        ("application/x-tar", Archive)
+    } else if starts(b"\x7fELF") {
+        ("application/x-executable", Executable)
+    } else if starts(b"MZ") {
+        ("application/vnd.microsoft.portable-executable", Executable)
+    } else if starts(b"\xfe\xed\xfa\xce")
+        || starts(b"\xfe\xed\xfa\xcf")
+        || starts(b"\xce\xfa\xed\xfe")
+        || starts(b"\xcf\xfa\xed\xfe")
+    {
+        ("application/x-mach-binary", Executable)
+    } else if starts(b"\0asm") {
+        ("application/wasm", Executable)
+    } else if starts(b"#!") {
+        ("text/x-shellscript", Code)
+    } else if starts(b"SQLite format 3\0") {
+        ("application/vnd.sqlite3", Other)
+    } else {
+        return None;
+    };
+
+    Some(detected)
+}
+
+/// ISO base media files (MP4, MOV, HEIC, AVIF, M4A) share the `ftyp` box,
+/// the major brand tells them apart
+fn sniff_iso_media(header: &[u8]) -> Option<(&'static str, FileCategory)> {
+    use FileCategory::*;
+
+    let brand = header.get(8..12)?;
+    let detected = match brand {
+        b"heic" | b"heix" | b"mif1" | b"msf1" => ("image/heic", Image),
+        b"avif" | b"avis" => ("image/avif", Image),
+        b"M4A " | b"M4B " => ("audio/mp4", Audio),
+        b"qt  " => ("video/quicktime", Video),
+        b"3gp4" | b"3gp5" | b"3g2a" => ("video/3gpp", Video),
+        _ => ("video/mp4", Video),
+    };
+    Some(detected)
+}
+
+fn from_extension(ext: &str) -> Option<(&'static str, FileCategory)> {
+    use FileCategory::*;
+
+    let detected = match ext {
+        // Images
+        "png" => ("image/png", Image),
+        "jpg" | "jpeg" | "jpe" => ("image/jpeg", Image),
+        "gif" => ("image/gif", Image),
+        "webp" => ("image/webp", Image),
+        "bmp" => ("image/bmp", Image),
+        "tif" | "tiff" => ("image/tiff", Image),
+        "ico" => ("image/x-icon", Image),
+        "svg" => ("image/svg+xml", Image),
+        "heic" | "heif" => ("image/heic", Image),
+        "avif" => ("image/avif", Image),
+
+        // Video
+        "mp4" | "m4v" => ("video/mp4", Video),
+        "mkv" => ("video/x-matroska", Video),
+        "webm" => ("video/webm", Video),
+        "avi" => ("video/x-msvideo", Video),
+        "mov" => ("video/quicktime", Video),
+        "wmv" => ("video/x-ms-wmv", Video),
+        "mpg" | "mpeg" => ("video/mpeg", Video),
+
+        // Audio
+        "mp3" => ("audio/mpeg", Audio),
+        "flac" => ("audio/flac", Audio),
+        "ogg" | "oga" | "opus" => ("audio/ogg", Audio),
+        "wav" => ("audio/wav", Audio),
+        "m4a" => ("audio/mp4", Audio),
+        "aac" => ("audio/aac", Audio),
+        "wma" => ("audio/x-ms-wma", Audio),
+
+        // Documents
+        "pdf" => ("application/pdf", Document),
+        "txt" | "log" => ("text/plain", Document),
+        "md" | "markdown" => ("text/markdown", Document),
+        "rtf" => ("application/rtf", Document),
+        "doc" => ("application/msword", Document),
+        "xls" => ("application/vnd.ms-excel", Document),
+        "ppt" => ("application/vnd.ms-powerpoint", Document),
+        "docx" => (
+            "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+            Document,
+        ),
+        "xlsx" => (
+            "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            Document,
+        ),
+        "pptx" => (
+            "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+            Document,
+        ),
+        "odt" => ("application/vnd.oasis.opendocument.text", Document),
+        "ods" => ("application/vnd.oasis.opendocument.spreadsheet", Document),
+        "odp" => ("application/vnd.oasis.opendocument.presentation", Document),
+        "epub" => ("application/epub+zip", Document),
+        "csv" => ("text/csv", Document),
+
+        // Archives
+        "zip" => ("application/zip", Archive),
+        "gz" | "tgz" => ("application/gzip", Archive),
+        "zst" | "tzst" => ("application/zstd", Archive),
+        "bz2" => ("application/x-bzip2", Archive),
+        "xz" => ("application/x-xz", Archive),
+        "7z" => ("application/x-7z-compressed", Archive),
+        "rar" => ("application/vnd.rar", Archive),
+        "tar" => ("application/x-tar", Archive),
+        "jar" => ("application/java-archive", Archive),
+
+        // Source code and markup
+        "rs" => ("text/x-rust", Code),
+        "py" => ("text/x-python", Code),
+        "js" | "mjs" | "cjs" => ("text/javascript", Code),
+        "ts" | "tsx" => ("text/x-typescript", Code),
+        "jsx" => ("text/jsx", Code),
+        "c" | "h" => ("text/x-c", Code),
+        "cpp" | "cc" | "cxx" | "hpp" | "hh" => ("text/x-c++", Code),
+        "go" => ("text/x-go", Code),
+        "java" => ("text/x-java", Code),
+        "kt" | "kts" => ("text/x-kotlin", Code),
+        "swift" => ("text/x-swift", Code),
+        "cs" => ("text/x-csharp", Code),
+        "rb" => ("text/x-ruby", Code),
+        "php" => ("application/x-httpd-php", Code),
+        "lua" => ("text/x-lua", Code),
+        "sh" | "bash" | "zsh" => ("text/x-shellscript", Code),
+        "sql" => ("application/sql", Code),
+        "html" | "htm" => ("text/html", Code),
+        "css" => ("text/css", Code),
+        "scss" | "sass" => ("text/x-scss", Code),
+        "json" => ("application/json", Code),
+        "toml" => ("application/toml", Code),
+        "yaml" | "yml" => ("application/yaml", Code),
+        "xml" => ("application/xml", Code),
+
+        // Executables
+        "exe" | "dll" => ("application/vnd.microsoft.portable-executable", Executable),
+        "so" => ("application/x-sharedlib", Executable),
+        "appimage" => ("application/x-executable", Executable),
+        "wasm" => ("application/wasm", Executable),
+        "deb" => ("application/vnd.debian.binary-package", Executable),
+        "rpm" => ("application/x-rpm", Executable),
+        "msi" => ("application/x-msi", Executable),
+
+        _ => return None,
+    };
+
+    Some(detected)
+}
+
+/// Heuristic used for extension-less files: no NUL bytes and valid UTF-8.
+/// A multi-byte sequence cut off at the end of the sniffed window is tolerated.
+fn looks_like_text(header: &[u8]) -> bool {
+    if header.is_empty() || header.contains(&0) {
+        return false;
+    }
+    match std::str::from_utf8(header) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    }
+}
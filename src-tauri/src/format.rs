@@ -0,0 +1,178 @@
+use chrono::{DateTime, Local, Utc};
+use once_cell::sync::Lazy;
+use std::fmt::Write;
+
+use crate::settings::{self, DateFormat, SizeUnits};
+
+const SI_LABELS: [&str; 7] = ["B", "kB", "MB", "GB", "TB", "PB", "EB"];
+const IEC_LABELS: [&str; 7] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+/// Dates older than this are shown as local dates in the relative style
+const RELATIVE_DAYS: i64 = 7;
+
+// Read once, the system locale doesn't change while the app runs
+static SYSTEM_LOCALE: Lazy<String> =
+    Lazy::new(|| sys_locale::get_locale().unwrap_or_else(|| "en-US".to_string()));
+
+/// Decimal and thousands separators of a locale
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberFormat {
+    pub decimal: char,
+    pub group: char,
+}
+
+impl NumberFormat {
+    /// Separators for a BCP 47 or POSIX locale name, e.g. "de-CH" or "fr_FR.UTF-8".
+    /// Only the common cases are covered, anything else gets the English ones.
+    pub fn for_locale(locale: &str) -> Self {
+        let locale = locale.split(['.', '@']).next().unwrap_or_default();
+        let mut parts = locale.split(['-', '_']);
+        let language = parts.next().unwrap_or_default().to_ascii_lowercase();
+        let region = parts
+            .find(|p| p.len() == 2 || (p.len() == 3 && p.bytes().all(|b| b.is_ascii_digit())))
+            .unwrap_or_default()
+            .to_ascii_uppercase();
+
+        let (decimal, group) = match (language.as_str(), region.as_str()) {
+            ("de" | "it" | "fr", "CH") | ("rm", _) => ('.', '\u{2019}'),
+            ("fr", _) => (',', '\u{202F}'), // narrow no-break space
+            (
+                "de" | "it" | "es" | "nl" | "pt" | "id" | "tr" | "da" | "el" | "ro" | "hr" | "sl"
+                | "sr",
+                _,
+            ) => (',', '.'),
+            (
+                "ru" | "uk" | "be" | "pl" | "cs" | "sk" | "sv" | "nb" | "no" | "nn" | "fi" | "hu"
+                | "bg" | "lt" | "lv" | "et",
+                _,
+            ) => (',', '\u{00A0}'),
+            _ => ('.', ','),
+        };
+        Self { decimal, group }
+    }
+
+    /// `value` rounded to `precision` decimals, with grouped thousands
+    pub fn number(&self, value: f64, precision: usize) -> String {
+        let plain = format!("{:.*}", precision, value);
+        let (integer, fraction) = match plain.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (plain.as_str(), None),
+        };
+        let (sign, digits) = match integer.strip_prefix('-') {
+            Some(digits) => ("-", digits),
+            None => ("", integer),
+        };
+
+        let mut formatted = sign.to_string();
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i) % 3 == 0 {
+                formatted.push(self.group);
+            }
+            formatted.push(digit);
+        }
+        if let Some(fraction) = fraction {
+            formatted.push(self.decimal);
+            formatted.push_str(fraction);
+        }
+        formatted
+    }
+}
+
+/// Formats sizes and dates the way the user configured them.
+/// Built once per response from the current settings and shared by all of its entries,
+/// so a change applies to the next listing and never to half of one.
+#[derive(Debug, Clone)]
+pub struct Formatter {
+    pub units: SizeUnits,
+    pub precision: usize,
+    pub numbers: NumberFormat,
+    pub date_format: DateFormat,
+    pub now: DateTime<Utc>,
+}
+
+impl Formatter {
+    pub fn current() -> Self {
+        let settings = settings::current();
+        let locale = settings.locale.as_deref().unwrap_or(SYSTEM_LOCALE.as_str());
+        Self {
+            units: settings.size_units,
+            precision: settings.size_precision as usize,
+            numbers: NumberFormat::for_locale(locale),
+            date_format: settings.date_format.clone(),
+            now: Utc::now(),
+        }
+    }
+
+    /// e.g. "512 B", "1.5 MiB" or "1,5 MB". Bytes are never shown with decimals.
+    pub fn size(&self, bytes: u64) -> String {
+        let (base, labels) = match self.units {
+            SizeUnits::Si => (1000.0, SI_LABELS),
+            SizeUnits::Iec => (1024.0, IEC_LABELS),
+        };
+        if (bytes as f64) < base {
+            return format!("{} {}", bytes, labels[0]);
+        }
+
+        let mut value = bytes as f64;
+        let mut unit = 0;
+        while value >= base && unit < labels.len() - 1 {
+            value /= base;
+            unit += 1;
+        }
+        // 1023.96 KiB would round to "1024.0 KiB", show "1.0 MiB" instead
+        let rounding = 10f64.powi(self.precision as i32);
+        if (value * rounding).round() / rounding >= base && unit < labels.len() - 1 {
+            value /= base;
+            unit += 1;
+        }
+        format!(
+            "{} {}",
+            self.numbers.number(value, self.precision),
+            labels[unit]
+        )
+    }
+
+    /// Date of a timestamp in seconds since the epoch, empty when unknown
+    pub fn date(&self, secs: Option<i64>) -> String {
+        let Some(date) = secs.and_then(|secs| DateTime::<Utc>::from_timestamp(secs, 0)) else {
+            return String::new();
+        };
+        match &self.date_format {
+            DateFormat::Local => local_date(date, "%Y-%m-%d %H:%M"),
+            DateFormat::Relative => {
+                relative_date(date, self.now).unwrap_or_else(|| local_date(date, "%Y-%m-%d %H:%M"))
+            }
+            DateFormat::Iso => date.to_rfc3339(),
+            DateFormat::Custom(pattern) => local_date(date, pattern),
+        }
+    }
+}
+
+fn local_date(date: DateTime<Utc>, pattern: &str) -> String {
+    let mut formatted = String::new();
+    // Patterns are validated with the settings, this only guards against a bad one slipping through
+    if write!(formatted, "{}", date.with_timezone(&Local).format(pattern)).is_err() {
+        return date.with_timezone(&Local).to_rfc3339();
+    }
+    formatted
+}
+
+/// "3 hours ago", or None for future and old dates
+fn relative_date(date: DateTime<Utc>, now: DateTime<Utc>) -> Option<String> {
+    let seconds = (now - date).num_seconds();
+    let plural = |count: i64, unit: &str| match count {
+        1 => format!("1 {} ago", unit),
+        _ => format!("{} {}s ago", count, unit),
+    };
+
+    match seconds {
+        // A few seconds in the future is clock skew between machines, not a real date
+        -60..60 => Some("just now".to_string()),
+        60..3600 => Some(plural(seconds / 60, "minute")),
+        3600..86400 => Some(plural(seconds / 3600, "hour")),
+        _ if seconds < 0 => None,
+        _ => Some(seconds / 86400)
+            .filter(|days| *days < RELATIVE_DAYS)
+            .map(|days| plural(days, "day")),
+    }
+}
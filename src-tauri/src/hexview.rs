@@ -0,0 +1,263 @@
+use memchr::memmem;
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+};
+use tauri::{command, AppHandle, Emitter};
+
+use crate::jobs;
+use crate::paths::decode_path;
+
+/// Event carrying a batch of search hits of a running hex search job
+pub const HEX_SEARCH_HIT_EVENT: &str = "hex-search-hit";
+
+const DEFAULT_BYTES_PER_ROW: usize = 16;
+const MAX_BYTES_PER_ROW: usize = 64;
+const MAX_PAGE_LEN: usize = 64 * 1024;
+
+/// Size of the blocks read while searching
+const SEARCH_CHUNK_LEN: usize = 1024 * 1024;
+const DEFAULT_MAX_HITS: usize = 10_000;
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct HexRow {
+    pub offset: u64,
+    pub hex: String,   // e.g. "7f 45 4c 46"
+    pub ascii: String, // non-printable bytes are shown as '.'
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct HexPage {
+    pub file_size: u64,
+    pub offset: u64,
+    pub length: usize, // bytes actually read, shorter than requested at the end of the file
+    pub rows: Vec<HexRow>,
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum SearchPattern {
+    Hex(String), // e.g. "7f 45 4c 46", "0x7f454c46"
+    Text(String),
+    TextUtf16(String), // little endian, as used by Windows binaries
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct HexSearchHits {
+    pub job_id: u64,
+    pub offsets: Vec<u64>,
+}
+
+/// Reads `length` bytes at `offset` and formats them as hex dump rows.
+/// Only the requested range is read, so paging through multi-GB files stays cheap.
+#[command]
+pub fn read_hex(
+    path: String,
+    offset: u64,
+    length: usize,
+    bytes_per_row: Option<usize>,
+) -> Result<HexPage, String> {
+    let bytes_per_row = bytes_per_row
+        .unwrap_or(DEFAULT_BYTES_PER_ROW)
+        .clamp(1, MAX_BYTES_PER_ROW);
+
+    let mut file =
+        File::open(decode_path(&path)).map_err(|e| format!("Failed to open {}: {:?}", path, e))?;
+    let file_size = file
+        .metadata()
+        .map_err(|e| format!("Failed to read metadata of {}: {:?}", path, e))?
+        .len();
+
+    let mut bytes = Vec::with_capacity(length.min(MAX_PAGE_LEN));
+    if offset < file_size {
+        file.seek(SeekFrom::Start(offset))
+            .map_err(|e| format!("Failed to seek in {}: {:?}", path, e))?;
+        file.take(length.min(MAX_PAGE_LEN) as u64)
+            .read_to_end(&mut bytes)
+            .map_err(|e| format!("Failed to read {}: {:?}", path, e))?;
+    }
+
+    let rows = bytes
+        .chunks(bytes_per_row)
+        .enumerate()
+        .map(|(i, row)| HexRow {
+            offset: offset + (i * bytes_per_row) as u64,
+            hex: row
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<Vec<_>>()
+                .join(" "),
+            ascii: row
+                .iter()
+                .map(|&b| {
+                    if b.is_ascii_graphic() || b == b' ' {
+                        b as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect(),
+        })
+        .collect();
+
+    Ok(HexPage {
+        file_size,
+        offset,
+        length: bytes.len(),
+        rows,
+    })
+}
+
+/// Searches the file for a byte pattern from `start_offset` as a background job.
+/// Hits are streamed in batches through `hex-search-hit` events, the job stops after
+/// `max_hits` (10000 by default). `ignore_case` only applies to ASCII letters.
+#[command]
+pub fn search_hex(
+    app: AppHandle,
+    path: String,
+    pattern: SearchPattern,
+    start_offset: Option<u64>,
+    ignore_case: Option<bool>,
+    max_hits: Option<usize>,
+) -> Result<u64, String> {
+    let ignore_case = ignore_case.unwrap_or(false);
+    let mut needle = pattern_bytes(&pattern)?;
+    if needle.is_empty() {
+        return Err("Search pattern is empty".to_string());
+    }
+    if ignore_case {
+        needle.make_ascii_lowercase();
+    }
+
+    let path = decode_path(&path);
+    let max_hits = max_hits.unwrap_or(DEFAULT_MAX_HITS);
+    let emitter = app.clone();
+
+    Ok(jobs::spawn_job(app, "hex-search", move |job| {
+        search_file(
+            &path,
+            &needle,
+            start_offset.unwrap_or(0),
+            ignore_case,
+            max_hits,
+            job,
+            |offsets| {
+                let _ = emitter.emit(
+                    HEX_SEARCH_HIT_EVENT,
+                    HexSearchHits {
+                        job_id: job.id(),
+                        offsets,
+                    },
+                );
+            },
+        )
+    }))
+}
+
+fn search_file(
+    path: &Path,
+    needle: &[u8],
+    start_offset: u64,
+    ignore_case: bool,
+    max_hits: usize,
+    job: &jobs::JobHandle,
+    mut on_hits: impl FnMut(Vec<u64>),
+) -> Result<(), String> {
+    let mut file = File::open(path).map_err(|e| format!("Failed to open {:?}: {:?}", path, e))?;
+    let file_size = file
+        .metadata()
+        .map_err(|e| format!("Failed to read metadata of {:?}: {:?}", path, e))?
+        .len();
+    file.seek(SeekFrom::Start(start_offset))
+        .map_err(|e| format!("Failed to seek in {:?}: {:?}", path, e))?;
+
+    let finder = memmem::Finder::new(needle);
+    // The last `needle.len() - 1` bytes of a block are kept for the next one,
+    // so matches spanning two reads are found as well
+    let overlap = needle.len() - 1;
+    let mut buffer = vec![0u8; SEARCH_CHUNK_LEN + overlap];
+    let mut filled = 0;
+    let mut buffer_offset = start_offset; // file offset of buffer[0]
+    let mut found = 0;
+
+    loop {
+        job.check_cancelled()?;
+        let read = read_full(&mut file, &mut buffer[filled..])
+            .map_err(|e| format!("Failed to read {:?}: {:?}", path, e))?;
+        if read == 0 {
+            break;
+        }
+        if ignore_case {
+            buffer[filled..filled + read].make_ascii_lowercase();
+        }
+        filled += read;
+
+        // A match needs `needle.len()` bytes, so none can start inside the kept overlap
+        let mut hits: Vec<u64> = finder
+            .find_iter(&buffer[..filled])
+            .map(|i| buffer_offset + i as u64)
+            .collect();
+        hits.truncate(max_hits - found);
+        found += hits.len();
+        if !hits.is_empty() {
+            on_hits(hits);
+        }
+        if found >= max_hits || filled < buffer.len() {
+            break;
+        }
+
+        job.progress(buffer_offset + filled as u64, file_size, Some(path));
+        let keep = filled - overlap;
+        buffer.copy_within(keep..filled, 0);
+        buffer_offset += keep as u64;
+        filled = overlap;
+    }
+
+    Ok(())
+}
+
+/// Like `read_exact`, but stops without error at the end of the file
+fn read_full(file: &mut File, buffer: &mut [u8]) -> std::io::Result<usize> {
+    let mut total = 0;
+    while total < buffer.len() {
+        match file.read(&mut buffer[total..]) {
+            Ok(0) => break,
+            Ok(n) => total += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(total)
+}
+
+fn pattern_bytes(pattern: &SearchPattern) -> Result<Vec<u8>, String> {
+    match pattern {
+        SearchPattern::Hex(hex) => parse_hex(hex),
+        SearchPattern::Text(text) => Ok(text.as_bytes().to_vec()),
+        SearchPattern::TextUtf16(text) => {
+            Ok(text.encode_utf16().flat_map(u16::to_le_bytes).collect())
+        }
+    }
+}
+
+/// Accepts space separated or contiguous hex digits, with optional `0x` prefixes
+fn parse_hex(input: &str) -> Result<Vec<u8>, String> {
+    let digits: String = input
+        .split_whitespace()
+        .map(|token| token.trim_start_matches("0x").trim_start_matches("0X"))
+        .collect();
+    if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(format!("Invalid hex pattern {:?}", input));
+    }
+    if !digits.len().is_multiple_of(2) {
+        return Err(format!("Odd number of hex digits in {:?}", input));
+    }
+
+    // Only ASCII hex digits are left, so every two-byte slice is a valid byte
+    Ok(digits
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).unwrap_or("00"), 16).unwrap_or(0))
+        .collect())
+}
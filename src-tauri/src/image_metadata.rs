@@ -0,0 +1,197 @@
+use chrono::{FixedOffset, Local, NaiveDate, TimeZone};
+use exif::{Exif, In, Tag, Value};
+use std::{fs::File, io::BufReader, path::Path};
+use tauri::command;
+
+use crate::paths::decode_path;
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct ImageMetadata {
+    pub width: u32,
+    pub height: u32,
+    pub format: Option<String>,
+    pub color_type: String, // e.g. "Rgb8", "Rgba16", "L8"
+    pub exif: Option<ExifData>,
+}
+
+#[derive(serde::Serialize, Debug, Clone, Default)]
+pub struct ExifData {
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    pub lens_model: Option<String>,
+    pub exposure_time: Option<String>, // formatted, e.g. "1/250 s"
+    pub f_number: Option<f64>,
+    pub iso: Option<u32>,
+    pub focal_length: Option<f64>,    // millimeters
+    pub orientation: Option<u32>,     // 1-8, see the EXIF Orientation tag
+    pub capture_date: Option<String>, // RFC3339, in the camera's offset when recorded
+    pub captured_at: Option<i64>,     // seconds since the Unix epoch
+    pub gps: Option<GpsPosition>,
+}
+
+#[derive(serde::Serialize, Debug, Clone, Copy)]
+pub struct GpsPosition {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: Option<f64>, // meters, negative below sea level
+}
+
+/// Returns dimensions, color type and EXIF fields of an image.
+/// Only headers are read, the pixel data is never decoded.
+#[command]
+pub fn get_image_metadata(path: String) -> Result<ImageMetadata, String> {
+    let file_path = decode_path(&path);
+    let reader = image::ImageReader::open(&file_path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|e| format!("Failed to open {}: {:?}", path, e))?;
+    let format = reader.format().map(|f| {
+        f.extensions_str()
+            .first()
+            .copied()
+            .unwrap_or_default()
+            .to_string()
+    });
+
+    let decoder = reader
+        .into_decoder()
+        .map_err(|e| format!("Unsupported image {}: {:?}", path, e))?;
+    let (width, height) = image::ImageDecoder::dimensions(&decoder);
+    let color_type = format!("{:?}", image::ImageDecoder::color_type(&decoder));
+
+    Ok(ImageMetadata {
+        width,
+        height,
+        format,
+        color_type,
+        exif: read_exif(&file_path),
+    })
+}
+
+/// Reads the EXIF block of JPEG, TIFF, PNG, WebP or HEIF files.
+/// Returns None if the file has no EXIF data.
+pub fn read_exif(path: &Path) -> Option<ExifData> {
+    let exif = load_exif(path)?;
+    let captured = capture_datetime(&exif);
+
+    Some(ExifData {
+        camera_make: ascii_field(&exif, Tag::Make),
+        camera_model: ascii_field(&exif, Tag::Model),
+        lens_model: ascii_field(&exif, Tag::LensModel),
+        exposure_time: exif
+            .get_field(Tag::ExposureTime, In::PRIMARY)
+            .map(|f| f.display_value().with_unit(&exif).to_string()),
+        f_number: rational_field(&exif, Tag::FNumber),
+        iso: uint_field(&exif, Tag::PhotographicSensitivity),
+        focal_length: rational_field(&exif, Tag::FocalLength),
+        orientation: uint_field(&exif, Tag::Orientation),
+        capture_date: captured.map(|dt| dt.to_rfc3339()),
+        captured_at: captured.map(|dt| dt.timestamp()),
+        gps: gps_position(&exif),
+    })
+}
+
+/// Capture time in seconds since the Unix epoch, used to sort photos by when they were taken
+pub fn capture_time(path: &Path) -> Option<i64> {
+    load_exif(path)
+        .as_ref()
+        .and_then(capture_datetime)
+        .map(|dt| dt.timestamp())
+}
+
+fn load_exif(path: &Path) -> Option<Exif> {
+    let file = File::open(path).ok()?;
+    exif::Reader::new()
+        .read_from_container(&mut BufReader::new(file))
+        .ok()
+}
+
+fn ascii_field(exif: &Exif, tag: Tag) -> Option<String> {
+    match &exif.get_field(tag, In::PRIMARY)?.value {
+        Value::Ascii(values) => values
+            .first()
+            .map(|bytes| {
+                String::from_utf8_lossy(bytes)
+                    .trim_matches(['\0', ' '])
+                    .to_string()
+            })
+            .filter(|s| !s.is_empty()),
+        _ => None,
+    }
+}
+
+fn uint_field(exif: &Exif, tag: Tag) -> Option<u32> {
+    exif.get_field(tag, In::PRIMARY)?.value.get_uint(0)
+}
+
+fn rational_field(exif: &Exif, tag: Tag) -> Option<f64> {
+    match &exif.get_field(tag, In::PRIMARY)?.value {
+        Value::Rational(values) => values.first().map(|r| r.to_f64()),
+        _ => None,
+    }
+}
+
+/// DateTimeOriginal, combined with OffsetTimeOriginal when the camera recorded it.
+/// Without an offset the time is assumed to be in the local timezone.
+fn capture_datetime(exif: &Exif) -> Option<chrono::DateTime<FixedOffset>> {
+    let field = exif
+        .get_field(Tag::DateTimeOriginal, In::PRIMARY)
+        .or_else(|| exif.get_field(Tag::DateTime, In::PRIMARY))?;
+    let Value::Ascii(values) = &field.value else {
+        return None;
+    };
+    let mut dt = exif::DateTime::from_ascii(values.first()?).ok()?;
+
+    if let Some(Value::Ascii(offset)) = exif
+        .get_field(Tag::OffsetTimeOriginal, In::PRIMARY)
+        .map(|f| &f.value)
+    {
+        if let Some(offset) = offset.first() {
+            let _ = dt.parse_offset(offset);
+        }
+    }
+
+    let naive = NaiveDate::from_ymd_opt(dt.year as i32, dt.month as u32, dt.day as u32)?
+        .and_hms_opt(dt.hour as u32, dt.minute as u32, dt.second as u32)?;
+
+    match dt.offset {
+        Some(minutes) => FixedOffset::east_opt(minutes as i32 * 60)?
+            .from_local_datetime(&naive)
+            .single(),
+        None => Local
+            .from_local_datetime(&naive)
+            .earliest()
+            .map(|local| local.fixed_offset()),
+    }
+}
+
+fn gps_position(exif: &Exif) -> Option<GpsPosition> {
+    let coordinate = |value_tag: Tag, ref_tag: Tag, negative_ref: &str| -> Option<f64> {
+        let Value::Rational(dms) = &exif.get_field(value_tag, In::PRIMARY)?.value else {
+            return None;
+        };
+        if dms.len() < 3 {
+            return None;
+        }
+        let degrees = dms[0].to_f64() + dms[1].to_f64() / 60.0 + dms[2].to_f64() / 3600.0;
+        let is_negative = ascii_field(exif, ref_tag).is_some_and(|r| r == negative_ref);
+        Some(if is_negative { -degrees } else { degrees })
+    };
+
+    let latitude = coordinate(Tag::GPSLatitude, Tag::GPSLatitudeRef, "S")?;
+    let longitude = coordinate(Tag::GPSLongitude, Tag::GPSLongitudeRef, "W")?;
+
+    // GPSAltitudeRef 1 means below sea level
+    let altitude = rational_field(exif, Tag::GPSAltitude).map(|altitude| {
+        if uint_field(exif, Tag::GPSAltitudeRef) == Some(1) {
+            -altitude
+        } else {
+            altitude
+        }
+    });
+
+    Some(GpsPosition {
+        latitude,
+        longitude,
+        altitude,
+    })
+}
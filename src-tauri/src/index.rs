@@ -1,90 +1,138 @@
 use arc_swap::ArcSwap;
 use blake3;
-use chrono::{DateTime, Utc};
 use once_cell::sync::Lazy;
 use rayon::prelude::*;
-use std::{collections::HashMap, sync::Arc, time::UNIX_EPOCH};
+use std::{collections::HashMap, fs, path::PathBuf, sync::Arc};
 use sysinfo::Disks;
 use walkdir::WalkDir;
 
+use crate::error::{ExplorerError, SkipReason, SkipSummary};
+use crate::filetype::{self, FileCategory};
+use crate::format::Formatter;
 use crate::models::FileInfo;
+use crate::paths;
+use crate::recent;
+use crate::settings;
 
 static FILE_INDEX: Lazy<ArcSwap<HashMap<String, Arc<FileInfo>>>> =
     Lazy::new(|| ArcSwap::new(Arc::new(HashMap::new())));
 
+/// Result of an index build
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct IndexSummary {
+    pub indexed: usize,
+    pub skipped: SkipSummary,
+}
+
 #[tauri::command]
-pub async fn build_index() -> Result<(), String> {
-    let new_index = tokio::task::spawn_blocking(|| {
-        let disks = Disks::new_with_refreshed_list();
+pub async fn build_index() -> Result<IndexSummary, ExplorerError> {
+    let (new_index, skipped) = tokio::task::spawn_blocking(|| {
+        let settings = settings::current();
+        let roots: Vec<PathBuf> = if settings.index_roots.is_empty() {
+            let disks = Disks::new_with_refreshed_list();
+            disks.list().iter().map(|d| d.mount_point().to_path_buf()).collect()
+        } else {
+            settings.index_roots.iter().map(|r| paths::decode_path(r)).collect()
+        };
         let mut combined_map = HashMap::new();
+        let mut skipped = SkipSummary::default();
 
-        for disk in disks.list() {
-            let root = disk.mount_point();
+        for root in &roots {
+            let root = root.as_path();
 
-            // Walk recursively, skip unreadable files
-            let entries: Vec<_> = WalkDir::new(&root)
+            // Walk recursively, unreadable folders are reported and their content left out
+            let mut entries = Vec::new();
+            let walker = WalkDir::new(root)
                 .into_iter()
-                .filter_map(|e| e.ok())
-                .filter(|e| e.file_type().is_file())
-                .collect();
+                .filter_entry(|e| !settings.is_excluded(e.path()));
+            for entry in walker {
+                match entry {
+                    Ok(entry) if entry.file_type().is_file() => entries.push(entry),
+                    Ok(entry) if entry.path_is_symlink() && fs::metadata(entry.path()).is_err() => {
+                        skipped.add_broken_symlink(entry.path())
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        let path = e.path().unwrap_or(root).to_path_buf();
+                        match e.into_io_error() {
+                            Some(io_error) => skipped.add_io(&path, &io_error),
+                            None => skipped.add(&path, SkipReason::Io, "Filesystem loop"),
+                        }
+                    }
+                }
+            }
 
             // Parallel processing with Rayon
-            let map: HashMap<_, _> = entries
+            let results: Vec<_> = entries
                 .par_iter()
-                .filter_map(|entry| {
+                .map(|entry| {
                     let path = entry.path();
-                    let name = path.file_name()?.to_str()?.to_string();
-                    let metadata = entry.metadata().ok()?;
-                    let file_size = metadata.len();
-
-                    let modification_date = metadata
-                        .modified()
-                        .ok()
-                        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
-                        .and_then(|d| {
-                            let timestamp = d.as_secs() as i64;
-                            DateTime::<Utc>::from_timestamp(timestamp, 0)
-                        })
-                        .map(|dt| dt.to_rfc3339())
-                        .unwrap_or_else(|| "Unknown".to_string());
-
-                    let lower_name = name.to_lowercase();
-                    let hash = blake3::hash(lower_name.as_bytes()).to_hex().to_string();
-
-                    Some((
-                        hash,
-                        Arc::new(FileInfo {
-                            file_name: name,
-                            file_size,
-                            modification_date,
-                            formatted_size: FileInfo::format_size(file_size),
-                            file_path: path.display().to_string(),
-                            lower_name,
-                            file_type: "file".to_string(), // Index only contains files (filtered on line 28)
-                        }),
-                    ))
+                    let metadata = entry.metadata().map_err(|e| {
+                        let reason = e.io_error().map_or(SkipReason::Io, SkipReason::from_io);
+                        (path, reason, e.to_string())
+                    })?;
+
+                    let mut info = FileInfo::from_metadata(
+                        paths::display_name(entry.file_name()),
+                        paths::encode_path(path),
+                        &metadata,
+                    );
+                    let hash = blake3::hash(info.lower_name.as_bytes()).to_hex().to_string();
+
+                    // Sniffing every file on every disk would be far too slow, only files
+                    // without a known extension are read
+                    if let Some(detected) = filetype::detect_fast(path) {
+                        info.mime_type = Some(detected.mime_type);
+                        info.category = Some(detected.category);
+                    }
+
+                    Ok((hash, Arc::new(info)))
                 })
                 .collect();
 
-            combined_map.extend(map);
+            for result in results {
+                match result {
+                    Ok((hash, info)) => {
+                        combined_map.insert(hash, info);
+                    }
+                    Err((path, reason, message)) => skipped.add(path, reason, message),
+                }
+            }
         }
 
-        Ok::<_, String>(combined_map)
+        (combined_map, skipped)
     })
-    .await
-    .map_err(|e| e.to_string())??;
+    .await?;
+
+    let indexed = new_index.len();
 
     // Atomic swap
     FILE_INDEX.store(Arc::new(new_index));
 
-    Ok(())
+    Ok(IndexSummary { indexed, skipped })
 }
 
+/// Searches the index by name, most used files first.
+/// `category` matches the listing filter of the same category, except for files whose
+/// extension contradicts their content: search goes by the extension, listings by the
+/// content (see `filetype::detect_fast`).
 #[tauri::command]
-pub async fn search_files(query: String) -> Result<Vec<FileInfo>, String> {
+pub async fn search_files(
+    app: tauri::AppHandle,
+    query: String,
+    category: Option<FileCategory>,
+) -> Result<Vec<FileInfo>, ExplorerError> {
     let query_lower = query.trim().to_lowercase();
 
-    if query_lower.is_empty() {
+    // Only names are indexed, a query with a separator can never match
+    if query_lower.contains(std::path::MAIN_SEPARATOR) || query_lower.contains('\0') {
+        return Err(ExplorerError::InvalidQuery(
+            "File names cannot contain path separators".to_string(),
+        ));
+    }
+
+    // An empty query is only meaningful when browsing a whole category
+    if query_lower.is_empty() && category.is_none() {
         return Ok(vec![]);
     }
 
@@ -93,20 +141,37 @@ pub async fn search_files(query: String) -> Result<Vec<FileInfo>, String> {
     let results = tokio::task::spawn_blocking(move || {
         let query_hash = blake3::hash(query_lower.as_bytes()).to_hex().to_string();
 
+        let matches_category =
+            |file: &FileInfo| category.is_none_or(|c| file.category == Some(c));
+
         // Exact hash match (only works for exact filename matches)
         if let Some(file) = index_snapshot.get(&query_hash) {
-            return vec![(*file).as_ref().clone()];
+            if matches_category(file) {
+                let mut file = (*file).as_ref().clone();
+                file.format(&Formatter::current());
+                return vec![file];
+            }
         }
 
         // Substring search fallback - search in lowercase file names
-        index_snapshot
+        let mut matches = index_snapshot
             .values()
-            .filter(|file| file.lower_name.contains(&query_lower))
+            .filter(|file| matches_category(file) && file.lower_name.contains(&query_lower))
             .map(|file| (*file).as_ref().clone())
-            .collect::<Vec<_>>()
+            .collect::<Vec<_>>();
+
+        // Files the user actually works with come first, the rest keep their order
+        let scores = recent::frecency_scores(&app);
+        let score = |file: &FileInfo| scores.get(&file.file_path).copied().unwrap_or(0.0);
+        matches.sort_by(|a, b| score(b).total_cmp(&score(a)));
+
+        let formatter = Formatter::current();
+        for file in &mut matches {
+            file.format(&formatter);
+        }
+        matches
     })
-    .await
-    .map_err(|e| format!("Task join error: {:?}", e))?;
+    .await?;
 
     Ok(results)
 }
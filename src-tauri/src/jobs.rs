@@ -0,0 +1,171 @@
+use once_cell::sync::Lazy;
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use tauri::{command, AppHandle, Emitter};
+
+/// Event emitted while a background job is running
+pub const JOB_PROGRESS_EVENT: &str = "job-progress";
+/// Event emitted once when a background job completes, fails or is cancelled
+pub const JOB_FINISHED_EVENT: &str = "job-finished";
+
+/// Minimum delay between two progress events of the same job
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+const COPY_BUFFER_SIZE: usize = 64 * 1024;
+
+static NEXT_JOB_ID: AtomicU64 = AtomicU64::new(1);
+
+// Cancellation flags of the jobs that are still running, keyed by job id
+static RUNNING_JOBS: Lazy<Mutex<HashMap<u64, Arc<AtomicBool>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct JobProgress {
+    pub job_id: u64,
+    pub kind: String,
+    pub processed_bytes: u64,
+    pub total_bytes: u64,
+    pub current_path: Option<String>,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct JobFinished {
+    pub job_id: u64,
+    pub kind: String,
+    pub cancelled: bool,
+    pub error: Option<String>,
+}
+
+/// Handle given to the work closure of a job to report progress and observe cancellation
+pub struct JobHandle {
+    id: u64,
+    kind: &'static str,
+    app: AppHandle,
+    cancelled: Arc<AtomicBool>,
+    last_emit: Mutex<Option<Instant>>,
+}
+
+impl JobHandle {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Returns an error once the job has been cancelled, so work loops can bail out with `?`
+    pub fn check_cancelled(&self) -> Result<(), String> {
+        if self.is_cancelled() {
+            Err("Job was cancelled".to_string())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Emits a progress event, throttled so tight loops don't flood the frontend
+    pub fn progress(&self, processed_bytes: u64, total_bytes: u64, current_path: Option<&Path>) {
+        let mut last_emit = self.last_emit.lock().unwrap();
+        let now = Instant::now();
+        let finished = processed_bytes >= total_bytes;
+        if !finished && last_emit.is_some_and(|t| now.duration_since(t) < PROGRESS_INTERVAL) {
+            return;
+        }
+        *last_emit = Some(now);
+
+        let _ = self.app.emit(
+            JOB_PROGRESS_EVENT,
+            JobProgress {
+                job_id: self.id,
+                kind: self.kind.to_string(),
+                processed_bytes,
+                total_bytes,
+                current_path: current_path.map(|p| p.display().to_string()),
+            },
+        );
+    }
+}
+
+/// Runs `work` on the blocking thread pool and returns the job id immediately.
+/// Progress and completion are reported through `job-progress` / `job-finished` events.
+pub fn spawn_job<F>(app: AppHandle, kind: &'static str, work: F) -> u64
+where
+    F: FnOnce(&JobHandle) -> Result<(), String> + Send + 'static,
+{
+    let id = NEXT_JOB_ID.fetch_add(1, Ordering::Relaxed);
+    let cancelled = Arc::new(AtomicBool::new(false));
+    RUNNING_JOBS.lock().unwrap().insert(id, cancelled.clone());
+
+    let handle = JobHandle {
+        id,
+        kind,
+        app,
+        cancelled,
+        last_emit: Mutex::new(None),
+    };
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = work(&handle);
+        RUNNING_JOBS.lock().unwrap().remove(&id);
+
+        let _ = handle.app.emit(
+            JOB_FINISHED_EVENT,
+            JobFinished {
+                job_id: id,
+                kind: kind.to_string(),
+                cancelled: handle.is_cancelled(),
+                error: result.err(),
+            },
+        );
+    });
+
+    id
+}
+
+/// Requests cancellation of a running job.
+/// Returns false if the job is unknown or already finished.
+#[command]
+pub fn cancel_job(job_id: u64) -> bool {
+    match RUNNING_JOBS.lock().unwrap().get(&job_id) {
+        Some(flag) => {
+            flag.store(true, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Copies `reader` into `writer`, adding to `processed` and reporting progress as it goes.
+/// Aborts with an error as soon as the job is cancelled.
+pub fn copy_with_progress<R: Read, W: Write>(
+    job: &JobHandle,
+    reader: &mut R,
+    writer: &mut W,
+    processed: &mut u64,
+    total: u64,
+    path: &Path,
+) -> Result<(), String> {
+    let mut buffer = vec![0u8; COPY_BUFFER_SIZE];
+    loop {
+        job.check_cancelled()?;
+        let read = reader
+            .read(&mut buffer)
+            .map_err(|e| format!("Failed to read {:?}: {:?}", path, e))?;
+        if read == 0 {
+            return Ok(());
+        }
+        writer
+            .write_all(&buffer[..read])
+            .map_err(|e| format!("Failed to write {:?}: {:?}", path, e))?;
+        *processed += read as u64;
+        job.progress(*processed, total, Some(path));
+    }
+}
@@ -1,11 +1,35 @@
 // Module declarations
 pub mod models;
+pub mod error;
+pub mod paths;
 pub mod index;
 pub mod disks;
 pub mod directories;
+pub mod jobs;
+pub mod archive;
+pub mod filetype;
+pub mod details;
+pub mod permissions;
+pub mod thumbnails;
+pub mod image_metadata;
+pub mod media;
+pub mod preview;
+pub mod hexview;
+pub mod checksums;
+pub mod compare;
+pub mod watcher;
+pub mod drives;
+pub mod fileops;
+pub mod sessions;
+pub mod config;
+pub mod bookmarks;
+pub mod settings;
+pub mod format;
+pub mod recent;
 
 // Re-export public types for convenience
 pub use models::{FileInfo, DiskInfo};
+pub use error::ExplorerError;
 
 // ------------------- Tauri run -------------------
 
@@ -13,15 +37,74 @@ pub use models::{FileInfo, DiskInfo};
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
+        .setup(|app| {
+            settings::init(app.handle());
+            bookmarks::init(app.handle());
+            recent::init(app.handle());
+            Ok(())
+        })
+        .register_asynchronous_uri_scheme_protocol(
+            thumbnails::THUMBNAIL_SCHEME,
+            thumbnails::handle_protocol,
+        )
         .invoke_handler(tauri::generate_handler![
             disks::list_disks,
+            disks::watch_disks,
+            disks::unwatch_disks,
+            drives::mount_drive,
+            drives::unmount_drive,
+            drives::eject_drive,
             directories::list_downloads,
             directories::list_documents,
             directories::list_pictures,
+            directories::list_pictures_by_date,
             directories::list_directory_contents,
+            directories::cancel_listing,
             index::search_files,
-            index::build_index
+            index::build_index,
+            jobs::cancel_job,
+            archive::create_archive,
+            archive::extract_archive,
+            details::get_file_details,
+            permissions::change_permissions,
+            permissions::change_owner,
+            thumbnails::prefetch_thumbnails,
+            image_metadata::get_image_metadata,
+            media::get_media_metadata,
+            preview::preview_file,
+            hexview::read_hex,
+            hexview::search_hex,
+            checksums::compute_checksums,
+            checksums::verify_checksums,
+            compare::compare_directories,
+            compare::plan_sync,
+            compare::execute_sync,
+            watcher::watch_directory,
+            watcher::unwatch_directory,
+            fileops::rename_file,
+            fileops::delete_file,
+            sessions::open_listing,
+            sessions::fetch_listing_range,
+            sessions::close_listing,
+            bookmarks::list_bookmarks,
+            bookmarks::add_bookmark,
+            bookmarks::update_bookmark,
+            bookmarks::remove_bookmark,
+            bookmarks::reorder_bookmarks,
+            settings::get_settings,
+            settings::update_settings,
+            config::list_config_problems,
+            recent::record_visit,
+            recent::list_recent,
+            recent::remove_recent,
+            recent::clear_recent,
+            recent::import_desktop_recent
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                recent::flush(app);
+            }
+        });
 }
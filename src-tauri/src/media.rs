@@ -0,0 +1,427 @@
+use lofty::prelude::*;
+use std::{
+    fs::File,
+    io::{BufReader, Read, Seek, SeekFrom},
+    path::Path,
+};
+use tauri::command;
+
+use crate::filetype::{self, DetectedType, FileCategory};
+use crate::paths::decode_path;
+
+/// Upper bound for the `moov` box we load in memory. Real files stay far below it.
+const MAX_MOOV_SIZE: u64 = 64 * 1024 * 1024;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct MediaMetadata {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub genre: Option<String>,
+    pub year: Option<u32>,
+    pub track: Option<u32>,
+    pub duration_ms: Option<u64>,
+    pub bitrate_kbps: Option<u32>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u8>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub video_codec: Option<String>, // e.g. "avc1", "hvc1", "V_VP9"
+    pub container: Option<String>,   // e.g. "mp4", "matroska", "webm", "mp3"
+}
+
+/// Returns tags and stream information of an audio or video file
+#[command]
+pub fn get_media_metadata(path: String) -> Result<MediaMetadata, String> {
+    read_media_metadata(&decode_path(&path)).ok_or(format!("No media information found in {}", path))
+}
+
+/// Reads audio tags (ID3v2, Vorbis comments, MP4 atoms) and container level
+/// video information (MP4/MOV, Matroska/WebM). Returns None for non-media files.
+pub fn read_media_metadata(path: &Path) -> Option<MediaMetadata> {
+    read_media_metadata_as(path, &filetype::detect(path)?)
+}
+
+/// Same as `read_media_metadata` for callers that already sniffed the file type
+pub fn read_media_metadata_as(path: &Path, detected: &DetectedType) -> Option<MediaMetadata> {
+    if !matches!(detected.category, FileCategory::Audio | FileCategory::Video) {
+        return None;
+    }
+
+    let mut metadata = MediaMetadata {
+        container: Some(container_name(&detected.mime_type)),
+        ..Default::default()
+    };
+    read_audio_tags(path, &mut metadata);
+
+    match detected.mime_type.as_str() {
+        "video/mp4" | "video/quicktime" | "video/3gpp" | "audio/mp4" => {
+            let _ = read_mp4(path, &mut metadata);
+        }
+        "video/x-matroska" | "video/webm" => {
+            let _ = read_matroska(path, &mut metadata);
+        }
+        _ => {}
+    }
+
+    Some(metadata)
+}
+
+fn container_name(mime_type: &str) -> String {
+    match mime_type {
+        "video/mp4" | "audio/mp4" => "mp4",
+        "video/quicktime" => "mov",
+        "video/3gpp" => "3gp",
+        "video/x-matroska" => "matroska",
+        "video/webm" => "webm",
+        "video/x-msvideo" => "avi",
+        "audio/mpeg" => "mp3",
+        "audio/flac" => "flac",
+        "audio/ogg" => "ogg",
+        "audio/wav" => "wav",
+        other => other.rsplit('/').next().unwrap_or(other),
+    }
+    .to_string()
+}
+
+fn read_audio_tags(path: &Path, metadata: &mut MediaMetadata) {
+    let Ok(tagged) = lofty::read_from_path(path) else {
+        return;
+    };
+
+    let properties = tagged.properties();
+    let duration = properties.duration().as_millis() as u64;
+    metadata.duration_ms = (duration > 0).then_some(duration);
+    metadata.bitrate_kbps = properties.overall_bitrate().or(properties.audio_bitrate());
+    metadata.sample_rate = properties.sample_rate();
+    metadata.channels = properties.channels();
+
+    if let Some(tag) = tagged.primary_tag().or_else(|| tagged.first_tag()) {
+        metadata.title = tag.title().map(|s| s.into_owned());
+        metadata.artist = tag.artist().map(|s| s.into_owned());
+        metadata.album = tag.album().map(|s| s.into_owned());
+        metadata.genre = tag.genre().map(|s| s.into_owned());
+        metadata.year = tag.year();
+        metadata.track = tag.track();
+    }
+}
+
+// ------------------- MP4 / QuickTime -------------------
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> Option<u64> {
+    let b = bytes.get(offset..offset + 8)?;
+    Some(u64::from_be_bytes([
+        b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7],
+    ]))
+}
+
+/// Iterates over the boxes contained in `data` as (type, payload) pairs
+fn mp4_boxes(data: &[u8]) -> impl Iterator<Item = (&[u8], &[u8])> {
+    let mut offset = 0usize;
+    std::iter::from_fn(move || {
+        let size = read_u32(data, offset)? as usize;
+        let kind = data.get(offset + 4..offset + 8)?;
+        let (header, size) = match size {
+            0 => (8, data.len() - offset),
+            1 => (16, read_u64(data, offset + 8)? as usize),
+            _ => (8, size),
+        };
+        if size < header {
+            return None;
+        }
+        let end = offset.checked_add(size)?;
+        let payload = data.get(offset + header..end)?;
+        offset = end;
+        Some((kind, payload))
+    })
+}
+
+fn find_box<'a>(data: &'a [u8], kind: &[u8]) -> Option<&'a [u8]> {
+    mp4_boxes(data)
+        .find(|(k, _)| *k == kind)
+        .map(|(_, payload)| payload)
+}
+
+/// Locates the top-level `moov` box without reading the media data around it
+fn load_moov(file: &mut File) -> Option<Vec<u8>> {
+    let file_len = file.metadata().ok()?.len();
+    let mut offset = 0u64;
+
+    // Sizes come from the file, a bogus 64-bit size must not overflow the offset
+    while offset.checked_add(8)? <= file_len {
+        file.seek(SeekFrom::Start(offset)).ok()?;
+        let mut header = [0u8; 16];
+        file.read_exact(&mut header[..8]).ok()?;
+
+        let mut size = read_u32(&header, 0)? as u64;
+        let mut header_len = 8;
+        if size == 1 {
+            file.read_exact(&mut header[8..]).ok()?;
+            size = read_u64(&header, 8)?;
+            header_len = 16;
+        } else if size == 0 {
+            size = file_len - offset;
+        }
+        if size < header_len {
+            return None;
+        }
+
+        if &header[4..8] == b"moov" {
+            let payload_len = size - header_len;
+            if payload_len > MAX_MOOV_SIZE {
+                return None;
+            }
+            let mut moov = vec![0u8; payload_len as usize];
+            file.read_exact(&mut moov).ok()?;
+            return Some(moov);
+        }
+        offset = offset.checked_add(size)?;
+    }
+
+    None
+}
+
+fn read_mp4(path: &Path, metadata: &mut MediaMetadata) -> Option<()> {
+    let mut file = File::open(path).ok()?;
+    let moov = load_moov(&mut file)?;
+
+    // mvhd: version(1) flags(3), then 32 or 64 bit times depending on the version
+    if let Some(mvhd) = find_box(&moov, b"mvhd") {
+        let (timescale, duration) = if mvhd.first() == Some(&1) {
+            (read_u32(mvhd, 20)?, read_u64(mvhd, 24)?)
+        } else {
+            (read_u32(mvhd, 12)?, read_u32(mvhd, 16)? as u64)
+        };
+        if timescale > 0 && metadata.duration_ms.is_none() {
+            metadata.duration_ms = Some((duration as u128 * 1000 / timescale as u128) as u64);
+        }
+    }
+
+    for (kind, trak) in mp4_boxes(&moov) {
+        if kind != b"trak" {
+            continue;
+        }
+        let Some(mdia) = find_box(trak, b"mdia") else {
+            continue;
+        };
+        // hdlr: version/flags(4), pre_defined(4), handler_type(4)
+        let is_video = find_box(mdia, b"hdlr").and_then(|h| h.get(8..12)) == Some(b"vide");
+        if !is_video {
+            continue;
+        }
+
+        // tkhd ends with width and height as 16.16 fixed point numbers
+        if let Some(tkhd) = find_box(trak, b"tkhd") {
+            if tkhd.len() >= 8 {
+                metadata.width = read_u32(tkhd, tkhd.len() - 8).map(|w| w >> 16);
+                metadata.height = read_u32(tkhd, tkhd.len() - 4).map(|h| h >> 16);
+            }
+        }
+
+        // stsd: version/flags(4), entry_count(4), then sample entries starting with size(4) + format(4)
+        metadata.video_codec = find_box(mdia, b"minf")
+            .and_then(|minf| find_box(minf, b"stbl"))
+            .and_then(|stbl| find_box(stbl, b"stsd"))
+            .and_then(|stsd| stsd.get(12..16))
+            .map(|codec| String::from_utf8_lossy(codec).trim().to_string());
+        break;
+    }
+
+    Some(())
+}
+
+// ------------------- Matroska / WebM -------------------
+
+const EBML_HEADER: u32 = 0x1A45_DFA3;
+const EBML_DOC_TYPE: u32 = 0x4282;
+const MKV_SEGMENT: u32 = 0x1853_8067;
+const MKV_INFO: u32 = 0x1549_A966;
+const MKV_TIMESTAMP_SCALE: u32 = 0x2A_D7B1;
+const MKV_DURATION: u32 = 0x4489;
+const MKV_TRACKS: u32 = 0x1654_AE6B;
+const MKV_TRACK_ENTRY: u32 = 0xAE;
+const MKV_TRACK_TYPE: u32 = 0x83;
+const MKV_CODEC_ID: u32 = 0x86;
+const MKV_VIDEO: u32 = 0xE0;
+const MKV_PIXEL_WIDTH: u32 = 0xB0;
+const MKV_PIXEL_HEIGHT: u32 = 0xBA;
+const MKV_CLUSTER: u32 = 0x1F43_B675;
+
+/// Reads an EBML variable length integer.
+/// IDs keep their length marker bits, sizes have them stripped.
+fn read_vint<R: Read>(reader: &mut R, keep_marker: bool) -> Option<(u64, bool)> {
+    let mut first = [0u8; 1];
+    reader.read_exact(&mut first).ok()?;
+    let length = first[0].leading_zeros() as usize + 1;
+    if length > 8 {
+        return None;
+    }
+
+    let mut value = if keep_marker {
+        first[0] as u64
+    } else {
+        (first[0] as u64) & (0xFF >> length)
+    };
+    let mut all_ones = value == (0xFF >> length);
+    for _ in 1..length {
+        reader.read_exact(&mut first).ok()?;
+        value = (value << 8) | first[0] as u64;
+        all_ones &= first[0] == 0xFF;
+    }
+
+    // A size with every value bit set means "unknown size"
+    Some((value, !keep_marker && all_ones))
+}
+
+/// Reads the next element header as (id, size); size is None when unknown
+fn read_element<R: Read>(reader: &mut R) -> Option<(u32, Option<u64>)> {
+    let (id, _) = read_vint(reader, true)?;
+    let (size, unknown) = read_vint(reader, false)?;
+    Some((id as u32, (!unknown).then_some(size)))
+}
+
+fn read_bytes<R: Read>(reader: &mut R, size: u64) -> Option<Vec<u8>> {
+    if size > 1024 * 1024 {
+        return None;
+    }
+    let mut bytes = vec![0u8; size as usize];
+    reader.read_exact(&mut bytes).ok()?;
+    Some(bytes)
+}
+
+fn ebml_uint(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0u64, |value, b| (value << 8) | *b as u64)
+}
+
+fn ebml_float(bytes: &[u8]) -> Option<f64> {
+    match bytes.len() {
+        4 => Some(f32::from_be_bytes(bytes.try_into().ok()?) as f64),
+        8 => Some(f64::from_be_bytes(bytes.try_into().ok()?)),
+        _ => None,
+    }
+}
+
+fn ebml_string(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes)
+        .trim_end_matches('\0')
+        .to_string()
+}
+
+/// Calls `visit` for each child of a master element spanning `size` bytes.
+/// The callback must consume exactly the child's payload or skip it.
+fn for_each_child<R, F>(reader: &mut R, size: u64, mut visit: F) -> Option<()>
+where
+    R: Read + Seek,
+    F: FnMut(&mut R, u32, u64) -> Option<bool>,
+{
+    let end = reader.stream_position().ok()?.checked_add(size)?;
+    while reader.stream_position().ok()? < end {
+        let (id, child_size) = read_element(reader)?;
+        let child_size = child_size?;
+        let start = reader.stream_position().ok()?;
+        if !visit(reader, id, child_size)? {
+            return Some(());
+        }
+        reader.seek(SeekFrom::Start(start.checked_add(child_size)?)).ok()?;
+    }
+    Some(())
+}
+
+fn read_matroska(path: &Path, metadata: &mut MediaMetadata) -> Option<()> {
+    let mut reader = BufReader::new(File::open(path).ok()?);
+
+    let (id, size) = read_element(&mut reader)?;
+    if id != EBML_HEADER {
+        return None;
+    }
+    for_each_child(&mut reader, size?, |r, id, size| {
+        if id == EBML_DOC_TYPE {
+            metadata.container = Some(ebml_string(&read_bytes(r, size)?));
+        }
+        Some(true)
+    })?;
+
+    let (id, _) = read_element(&mut reader)?;
+    if id != MKV_SEGMENT {
+        return None;
+    }
+
+    // Segment size is often unknown for live recordings, walk until the first cluster
+    let mut timestamp_scale = 1_000_000u64;
+    let mut duration = None;
+    while let Some((id, Some(size))) = read_element(&mut reader) {
+        let start = reader.stream_position().ok()?;
+
+        match id {
+            MKV_INFO => {
+                for_each_child(&mut reader, size, |r, id, size| {
+                    match id {
+                        MKV_TIMESTAMP_SCALE => timestamp_scale = ebml_uint(&read_bytes(r, size)?),
+                        MKV_DURATION => duration = ebml_float(&read_bytes(r, size)?),
+                        _ => {}
+                    }
+                    Some(true)
+                });
+            }
+            MKV_TRACKS => {
+                for_each_child(&mut reader, size, |r, id, size| {
+                    if id == MKV_TRACK_ENTRY {
+                        read_matroska_track(r, size, metadata);
+                    }
+                    // Only the first video track is reported
+                    Some(metadata.video_codec.is_none())
+                });
+            }
+            MKV_CLUSTER => break,
+            _ => {}
+        }
+        reader.seek(SeekFrom::Start(start.checked_add(size)?)).ok()?;
+    }
+
+    // Duration is expressed in TimestampScale units (nanoseconds per tick)
+    if let Some(duration) = duration {
+        metadata.duration_ms = Some((duration * timestamp_scale as f64 / 1_000_000.0) as u64);
+    }
+    Some(())
+}
+
+fn read_matroska_track<R: Read + Seek>(reader: &mut R, size: u64, metadata: &mut MediaMetadata) {
+    let mut is_video = false;
+    let mut codec = None;
+    let mut dimensions = (None, None);
+
+    for_each_child(reader, size, |r, id, size| {
+        match id {
+            MKV_TRACK_TYPE => is_video = ebml_uint(&read_bytes(r, size)?) == 1,
+            MKV_CODEC_ID => codec = Some(ebml_string(&read_bytes(r, size)?)),
+            MKV_VIDEO => {
+                for_each_child(r, size, |r, id, size| {
+                    match id {
+                        MKV_PIXEL_WIDTH => {
+                            dimensions.0 = Some(ebml_uint(&read_bytes(r, size)?) as u32)
+                        }
+                        MKV_PIXEL_HEIGHT => {
+                            dimensions.1 = Some(ebml_uint(&read_bytes(r, size)?) as u32)
+                        }
+                        _ => {}
+                    }
+                    Some(true)
+                });
+            }
+            _ => {}
+        }
+        Some(true)
+    });
+
+    if is_video {
+        metadata.video_codec = codec;
+        metadata.width = dimensions.0;
+        metadata.height = dimensions.1;
+    }
+}
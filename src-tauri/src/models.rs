@@ -1,88 +1,245 @@
 use chrono::{DateTime, Utc};
-use std::time::UNIX_EPOCH;
+use std::{
+    fs,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::error::ExplorerError;
+use crate::filetype::FileCategory;
+use crate::format::Formatter;
+use crate::media::MediaMetadata;
+
+/// Version of the serialized `FileInfo` layout.
+/// 1: string `modification_date` only, 2: typed kind and epoch timestamps.
+pub const FILE_INFO_VERSION: u32 = 2;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum FileKind {
+    File,
+    Directory,
+    Symlink,
+    #[serde(other)]
+    Unknown,
+}
+
+impl FileKind {
+    pub fn from_metadata(metadata: &fs::Metadata) -> Self {
+        match (metadata.is_dir(), metadata.is_file(), metadata.is_symlink()) {
+            (true, _, _) => Self::Directory,
+            (_, true, _) => Self::File,
+            (_, _, true) => Self::Symlink,
+            _ => Self::Unknown,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::File => "file",
+            Self::Directory => "directory",
+            Self::Symlink => "symlink",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+/// Converts a filesystem timestamp to seconds since the Unix epoch.
+/// Times before 1970 are returned as negative values.
+pub fn epoch_seconds(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    }
+}
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[serde(into = "FileInfoWire", from = "FileInfoWire")]
 pub struct FileInfo {
     pub file_name: String,
     pub file_size: u64,
-    pub modification_date: String,
-    pub formatted_size: String,
     pub file_path: String,
     pub lower_name: String, // precomputed lowercase name for faster search
-    pub file_type: String, // "file", "directory", "symlink", or "unknown"
+    pub kind: FileKind,
+    // Seconds since the Unix epoch, None when the platform or filesystem doesn't record them
+    pub modified: Option<i64>,
+    pub created: Option<i64>,
+    pub accessed: Option<i64>,
+    pub mime_type: Option<String>, // detected from magic bytes / extension, None for directories
+    pub category: Option<FileCategory>,
+    pub media: Option<MediaMetadata>, // audio/video columns, only filled when requested
+    pub error: Option<ExplorerError>, // set when the entry is listed but couldn't be read
+    // Size and modification date as the user configured them, filled by `format`
+    pub formatted_size: String,
+    pub formatted_date: String,
 }
 
 impl FileInfo {
     pub fn new(
         file_name: String,
         file_size: u64,
-        modification_date: String,
+        modified: Option<i64>,
         file_path: String,
-        file_type: String,
+        kind: FileKind,
     ) -> Self {
         let lower_name = file_name.to_lowercase();
         Self {
             file_name,
             file_size,
-            modification_date,
-            formatted_size: Self::format_size(file_size),
             file_path,
             lower_name,
-            file_type,
-        }
-    }
-
-    pub fn format_size(bytes: u64) -> String {
-        const KB: u64 = 1024;
-        const MB: u64 = KB * 1024;
-        const GB: u64 = MB * 1024;
-        const TB: u64 = GB * 1024;
-
-        match bytes {
-            0..KB => format!("{} B", bytes),
-            KB..MB => format!("{:.1} KB", bytes as f64 / KB as f64),
-            MB..GB => format!("{:.1} MB", bytes as f64 / MB as f64),
-            GB..TB => format!("{:.1} GB", bytes as f64 / GB as f64),
-            TB.. => format!("{:.1} TB", bytes as f64 / TB as f64),
+            kind,
+            modified,
+            created: None,
+            accessed: None,
+            mime_type: None,
+            category: None,
+            media: None,
+            error: None,
+            formatted_size: String::new(),
+            formatted_date: String::new(),
         }
     }
 
     pub fn from_metadata(
         file_name: String,
         file_path: String,
-        metadata: &std::fs::Metadata,
+        metadata: &fs::Metadata,
     ) -> Self {
-        let file_size = metadata.len();
-        let modification_date = metadata
-            .modified()
-            .ok()
-            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
-            .and_then(|d| {
-                let timestamp = d.as_secs() as i64;
-                DateTime::<Utc>::from_timestamp(timestamp, 0)
-            })
+        let mut info = Self::new(
+            file_name,
+            metadata.len(),
+            metadata.modified().ok().map(epoch_seconds),
+            file_path,
+            FileKind::from_metadata(metadata),
+        );
+        info.created = metadata.created().ok().map(epoch_seconds);
+        info.accessed = metadata.accessed().ok().map(epoch_seconds);
+        info
+    }
+
+    /// Entry that showed up in a listing but whose metadata couldn't be read
+    pub fn unreadable(file_name: String, file_path: String, error: ExplorerError) -> Self {
+        let mut info = Self::new(file_name, 0, None, file_path, FileKind::Unknown);
+        info.error = Some(error);
+        info
+    }
+
+    /// Fills the `formatted_*` fields. Every entry of a response is formatted with the
+    /// same `Formatter`, so relative dates agree on what "now" is.
+    pub fn format(&mut self, formatter: &Formatter) {
+        self.formatted_size = formatter.size(self.file_size);
+        self.formatted_date = formatter.date(self.modified);
+    }
+
+    /// RFC3339 modification date as the frontend expects it, or "Unknown"
+    pub fn modification_date(&self) -> String {
+        self.modified
+            .and_then(|secs| DateTime::<Utc>::from_timestamp(secs, 0))
             .map(|dt| dt.to_rfc3339())
-            .unwrap_or_else(|| "Unknown".to_string());
-
-        // Determine file type from metadata
-        let file_type = if metadata.is_dir() {
-            "directory".to_string()
-        } else if metadata.is_file() {
-            "file".to_string()
-        } else if metadata.is_symlink() {
-            "symlink".to_string()
-        } else {
-            "unknown".to_string()
-        };
+            .unwrap_or_else(|| "Unknown".to_string())
+    }
+}
+
+/// Serialized form of `FileInfo`.
+/// Keeps the string `file_type` and `modification_date` fields the frontend reads,
+/// and accepts version 1 payloads that only carry the RFC3339 date.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct FileInfoWire {
+    #[serde(default = "legacy_file_info_version")]
+    version: u32,
+    file_name: String,
+    file_size: u64,
+    #[serde(default)]
+    modification_date: String,
+    #[serde(default)]
+    formatted_size: String,
+    #[serde(default)]
+    formatted_date: String,
+    file_path: String,
+    #[serde(default)]
+    lower_name: String,
+    file_type: FileKind,
+    #[serde(default)]
+    modified: Option<i64>,
+    #[serde(default)]
+    created: Option<i64>,
+    #[serde(default)]
+    accessed: Option<i64>,
+    #[serde(default)]
+    mime_type: Option<String>,
+    #[serde(default)]
+    category: Option<FileCategory>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    media: Option<MediaMetadata>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<ExplorerError>,
+}
 
-        Self::new(file_name, file_size, modification_date, file_path, file_type)
+fn legacy_file_info_version() -> u32 {
+    1
+}
+
+impl From<FileInfo> for FileInfoWire {
+    fn from(info: FileInfo) -> Self {
+        Self {
+            version: FILE_INFO_VERSION,
+            modification_date: info.modification_date(),
+            formatted_size: info.formatted_size,
+            formatted_date: info.formatted_date,
+            file_name: info.file_name,
+            file_size: info.file_size,
+            file_path: info.file_path,
+            lower_name: info.lower_name,
+            file_type: info.kind,
+            modified: info.modified,
+            created: info.created,
+            accessed: info.accessed,
+            mime_type: info.mime_type,
+            category: info.category,
+            media: info.media,
+            error: info.error,
+        }
+    }
+}
+
+impl From<FileInfoWire> for FileInfo {
+    fn from(wire: FileInfoWire) -> Self {
+        // Version 1 only carried the RFC3339 string
+        let modified = wire.modified.or_else(|| {
+            DateTime::parse_from_rfc3339(&wire.modification_date)
+                .ok()
+                .map(|dt| dt.timestamp())
+        });
+
+        let mut info = Self::new(
+            wire.file_name,
+            wire.file_size,
+            modified,
+            wire.file_path,
+            wire.file_type,
+        );
+        info.created = wire.created;
+        info.accessed = wire.accessed;
+        info.mime_type = wire.mime_type;
+        info.category = wire.category;
+        info.media = wire.media;
+        info.error = wire.error;
+        info.formatted_size = wire.formatted_size;
+        info.formatted_date = wire.formatted_date;
+        info
     }
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Debug)]
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
 pub struct DiskInfo {
-    pub name: String,
+    pub name: String, // display name: label, "Local Disk (C:)", or derived from the mount point
     pub kind: String,
+    pub mount_point: String,
+    pub file_system: String, // e.g. "ext4", "vfat", "NTFS"
+    pub device_path: Option<String>, // e.g. "/dev/sdb1", None on Windows and for virtual filesystems
+    pub label: Option<String>,
+    pub is_removable: bool,
+    pub is_read_only: bool,
     pub total_space: u64,
     pub available_space: u64,
     pub used_space: u64,
@@ -92,61 +249,119 @@ pub struct DiskInfo {
 }
 
 impl DiskInfo {
-    pub fn new(disk: &sysinfo::Disk) -> Self {
+    pub fn new(disk: &sysinfo::Disk, formatter: &Formatter) -> Self {
         let total = disk.total_space();
         let available = disk.available_space();
-        let used = total - available;
-
-        // Get disk name - always use English format from mount point on Windows
-        let name = {
-            let mount_point = disk.mount_point().to_string_lossy().to_string();
-            
-            if !mount_point.is_empty() {
-                // Extract drive letter from mount point on Windows
-                // Mount points on Windows are typically "C:\", "D:\", etc.
-                let cleaned = mount_point.trim_end_matches('\\').trim_end_matches('/');
-                
-                // Check if it's a Windows drive letter format (C:, D:, etc.)
-                if cleaned.len() >= 2 {
-                    let first_char = cleaned.chars().next().unwrap();
-                    let second_char = cleaned.chars().nth(1);
-                    
-                    // Pattern: Single letter followed by colon (C:, D:, etc.)
-                    if first_char.is_alphabetic() && second_char == Some(':') {
-                        let drive_letter = cleaned.chars().take(2).collect::<String>();
-                        format!("Local Disk ({})", drive_letter)
-                    } else {
-                        // Not a standard drive letter, use cleaned mount point
-                        cleaned.to_string()
-                    }
-                } else if cleaned.len() == 1 && cleaned.chars().next().unwrap().is_alphabetic() {
-                    // Single letter, add colon
-                    format!("Local Disk ({}:)", cleaned)
-                } else {
-                    // Fallback: use mount point as-is
-                    mount_point
-                }
-            } else {
-                // Fallback: try disk name, or generate from disk kind
-                let disk_name = disk.name().to_string_lossy().to_string();
-                if !disk_name.is_empty() {
-                    disk_name
-                } else {
-                    format!("Disk ({:?})", disk.kind())
-                }
-            }
-        };
+        // Some filesystems (btrfs, network shares) can report more available than total
+        let used = total.saturating_sub(available);
+
+        let mount_point = disk.mount_point().to_string_lossy().to_string();
+        let disk_name = disk.name().to_string_lossy().to_string();
+        let device_path = Some(disk_name.clone()).filter(|name| name.starts_with("/dev/"));
+        let label = disk_label(&disk_name, device_path.as_deref());
 
         Self {
-            name,
+            name: display_name(disk, &mount_point, label.as_deref()),
             kind: format!("{:?}", disk.kind()),
+            mount_point,
+            file_system: disk.file_system().to_string_lossy().to_string(),
+            device_path,
+            label,
+            is_removable: disk.is_removable(),
+            is_read_only: disk.is_read_only(),
             total_space: total,
             available_space: available,
             used_space: used,
-            formatted_total: FileInfo::format_size(total),
-            formatted_available: FileInfo::format_size(available),
-            formatted_used: FileInfo::format_size(used),
+            formatted_total: formatter.size(total),
+            formatted_available: formatter.size(available),
+            formatted_used: formatter.size(used),
+        }
+    }
+}
+
+fn display_name(disk: &sysinfo::Disk, mount_point: &str, label: Option<&str>) -> String {
+    // Always use the English format from the mount point on Windows, the volume name
+    // is only exposed through `label` there
+    if let Some(label) = label.filter(|_| !cfg!(windows)) {
+        return label.to_string();
+    }
+    if mount_point.is_empty() {
+        // Fallback: try disk name, or generate from disk kind
+        let disk_name = disk.name().to_string_lossy().to_string();
+        return if !disk_name.is_empty() {
+            disk_name
+        } else {
+            format!("Disk ({:?})", disk.kind())
+        };
+    }
+
+    // Extract drive letter from mount point on Windows
+    // Mount points on Windows are typically "C:\", "D:\", etc.
+    let cleaned = mount_point.trim_end_matches('\\').trim_end_matches('/');
+    let mut chars = cleaned.chars();
+    match (chars.next(), chars.next(), chars.next()) {
+        // Pattern: Single letter followed by colon (C:, D:, etc.)
+        (Some(letter), Some(':'), None) if letter.is_alphabetic() => {
+            format!("Local Disk ({}:)", letter)
         }
+        // Single letter, add colon
+        (Some(letter), None, _) if letter.is_alphabetic() => format!("Local Disk ({}:)", letter),
+        // The root filesystem on Linux and macOS
+        (None, _, _) => "File System".to_string(),
+        // Removable drives are mounted under /media/<user>/<name> or /run/media/<user>/<name>,
+        // their last component is the most meaningful part
+        _ => cleaned
+            .rsplit(['/', '\\'])
+            .next()
+            .filter(|last| !last.is_empty())
+            .unwrap_or(cleaned)
+            .to_string(),
     }
 }
 
+/// Filesystem label: from `/dev/disk/by-label` on Linux, the volume name on Windows
+#[cfg(target_os = "linux")]
+fn disk_label(_disk_name: &str, device_path: Option<&str>) -> Option<String> {
+    let device = fs::canonicalize(device_path?).ok()?;
+    fs::read_dir("/dev/disk/by-label")
+        .ok()?
+        .flatten()
+        .find(|entry| fs::canonicalize(entry.path()).is_ok_and(|target| target == device))
+        .map(|entry| unescape_label(&entry.file_name().to_string_lossy()))
+}
+
+#[cfg(windows)]
+fn disk_label(disk_name: &str, _device_path: Option<&str>) -> Option<String> {
+    Some(disk_name.to_string()).filter(|name| !name.is_empty())
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn disk_label(_disk_name: &str, _device_path: Option<&str>) -> Option<String> {
+    None
+}
+
+/// udev escapes spaces and other unsafe characters in link names as `\xHH`
+#[cfg(target_os = "linux")]
+fn unescape_label(name: &str) -> String {
+    let bytes = name.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i..i + 4)
+            .filter(|s| s.starts_with(b"\\x"))
+            .and_then(|s| std::str::from_utf8(&s[2..]).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 4;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
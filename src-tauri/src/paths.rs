@@ -0,0 +1,167 @@
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+
+/// Marks a path that isn't valid UTF-8. The rest of the string is the raw path with the
+/// invalid bytes and `%` percent-escaped. NUL can't appear in a real path, so plain
+/// paths are never mistaken for escaped ones.
+pub const RAW_PATH_PREFIX: &str = "\0raw:";
+
+/// String form of a path sent to the frontend, decoded back with `decode_path`.
+/// UTF-8 paths are sent unchanged.
+pub fn encode_path(path: &Path) -> String {
+    if let Some(path) = path.to_str() {
+        return path.to_string();
+    }
+
+    let mut encoded = RAW_PATH_PREFIX.to_string();
+    for chunk in path_to_bytes(path).utf8_chunks() {
+        encoded.push_str(&chunk.valid().replace('%', "%25"));
+        for byte in chunk.invalid() {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// Path received from the frontend, either plain or produced by `encode_path`
+pub fn decode_path(path: &str) -> PathBuf {
+    match path.strip_prefix(RAW_PATH_PREFIX) {
+        Some(escaped) => bytes_to_path(percent_decode(escaped)),
+        None => PathBuf::from(path),
+    }
+}
+
+/// Name shown to the user. Names that aren't UTF-8 are usually in a legacy 8-bit
+/// encoding, decoding them as Latin-1 keeps accented letters readable and searchable.
+pub fn display_name(name: &OsStr) -> String {
+    match name.to_str() {
+        Some(name) => name.to_string(),
+        None if cfg!(unix) => path_to_bytes(Path::new(name))
+            .iter()
+            .map(|&b| b as char)
+            .collect(),
+        None => name.to_string_lossy().into_owned(),
+    }
+}
+
+/// Display name of the last component of `path`, empty for roots
+pub fn file_name(path: &Path) -> String {
+    path.file_name().map(display_name).unwrap_or_default()
+}
+
+/// Dot files everywhere, plus entries with the hidden attribute on Windows
+pub fn is_hidden(name: &OsStr, metadata: Option<&std::fs::Metadata>) -> bool {
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+        if metadata.is_some_and(|m| m.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0) {
+            return true;
+        }
+    }
+    #[cfg(not(windows))]
+    let _ = metadata;
+    path_to_bytes(Path::new(name)).first() == Some(&b'.')
+}
+
+pub fn percent_decode(input: &str) -> Vec<u8> {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(value)) => {
+                decoded.push(value);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    decoded
+}
+
+#[cfg(unix)]
+pub fn path_to_bytes(path: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    path.as_os_str().as_bytes().to_vec()
+}
+
+#[cfg(not(unix))]
+pub fn path_to_bytes(path: &Path) -> Vec<u8> {
+    path.to_string_lossy().replace('\\', "/").into_bytes()
+}
+
+#[cfg(unix)]
+pub fn bytes_to_path(bytes: Vec<u8>) -> PathBuf {
+    use std::os::unix::ffi::OsStringExt;
+    PathBuf::from(std::ffi::OsString::from_vec(bytes))
+}
+
+#[cfg(not(unix))]
+pub fn bytes_to_path(bytes: Vec<u8>) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn raw(bytes: &[u8]) -> PathBuf {
+        bytes_to_path(bytes.to_vec())
+    }
+
+    #[test]
+    fn utf8_paths_are_unchanged() {
+        for path in [
+            "/home/user/Documents",
+            "/tmp/100%",
+            "/tmp/%41%FF",
+            "/tmp/raw:name",
+            "/tmp/été 日本",
+            "",
+        ] {
+            assert_eq!(encode_path(Path::new(path)), path);
+            assert_eq!(decode_path(path), PathBuf::from(path));
+        }
+    }
+
+    #[test]
+    fn non_utf8_paths_round_trip() {
+        for path in [
+            raw(b"/tmp/caf\xe9"),
+            raw(b"/tmp/\xff\xfe"),
+            raw(b"/tmp/100%\xe9"),
+            raw(b"/tmp/%41\xe9%FF"),
+            raw(b"/tmp/\xe9/raw:%25"),
+            raw(b"/tmp/\xc3"), // truncated UTF-8 sequence
+        ] {
+            let encoded = encode_path(&path);
+            assert!(encoded.starts_with(RAW_PATH_PREFIX), "{:?}", encoded);
+            assert_eq!(decode_path(&encoded), path, "{:?}", encoded);
+        }
+    }
+
+    #[test]
+    fn escapes_percent_and_invalid_bytes() {
+        assert_eq!(
+            encode_path(&raw(b"/tmp/5%\xe9")),
+            format!("{}/tmp/5%25%E9", RAW_PATH_PREFIX)
+        );
+    }
+
+    #[test]
+    fn encoded_paths_contain_the_prefix_only_at_the_start() {
+        let encoded = encode_path(&raw(b"/tmp/\xe9"));
+        let name = &encoded[RAW_PATH_PREFIX.len()..];
+        assert!(!name.contains('\0'));
+    }
+}
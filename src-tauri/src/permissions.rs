@@ -0,0 +1,537 @@
+use std::path::{Path, PathBuf};
+use tauri::command;
+use walkdir::WalkDir;
+
+use crate::paths::{decode_path, encode_path};
+
+/// Outcome of a permission or ownership change on a single path.
+/// In a dry run nothing is modified and `error` holds the failure we expect, if any.
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct AttributeChange {
+    pub path: String,
+    pub before: String, // "rwxr-xr-x" for chmod, "owner:group" for chown
+    pub after: String,
+    pub error: Option<String>,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct ChangeReport {
+    pub dry_run: bool,
+    pub changes: Vec<AttributeChange>,
+    pub failed: usize,
+}
+
+impl ChangeReport {
+    fn new(dry_run: bool, changes: Vec<AttributeChange>) -> Self {
+        let failed = changes.iter().filter(|c| c.error.is_some()).count();
+        Self {
+            dry_run,
+            changes,
+            failed,
+        }
+    }
+}
+
+// ------------------- Mode parsing -------------------
+
+/// A chmod mode, either absolute octal ("755") or symbolic ("u+x,go-w,a=rX")
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModeSpec {
+    Octal {
+        mode: u32,
+        // Like chmod, directories keep their setuid/setgid bits unless the mode has
+        // five or more digits ("00755") or sets them
+        keeps_dir_ids: bool,
+    },
+    Symbolic(Vec<ModeClause>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModeClause {
+    who: Option<u32>, // mask of the bits the clause may touch, None if not given
+    umask: u32,       // limits the bits set or cleared when `who` isn't given
+    actions: Vec<(char, Vec<char>)>,
+}
+
+const WHO_USER: u32 = 0o4700;
+const WHO_GROUP: u32 = 0o2070;
+const WHO_OTHER: u32 = 0o1007;
+const WHO_ALL: u32 = WHO_USER | WHO_GROUP | WHO_OTHER;
+const SET_IDS: u32 = 0o6000;
+
+impl ModeSpec {
+    /// Parses a mode the way chmod(1) does: clauses without "who" ("+w") leave
+    /// the bits of `umask` alone
+    pub fn parse(spec: &str, umask: u32) -> Result<Self, String> {
+        let spec = spec.trim();
+        if spec.is_empty() {
+            return Err("Empty permission mode".to_string());
+        }
+
+        if spec.chars().all(|c| c.is_ascii_digit()) {
+            let mode = u32::from_str_radix(spec, 8)
+                .ok()
+                .filter(|mode| *mode <= 0o7777)
+                .ok_or(format!("Invalid octal mode: {}", spec))?;
+            return Ok(Self::Octal {
+                mode,
+                keeps_dir_ids: spec.len() < 5,
+            });
+        }
+
+        spec.split(',')
+            .map(|clause| ModeClause::parse(clause, umask))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Self::Symbolic)
+    }
+
+    /// Computes the new mode of an entry from its current mode
+    pub fn apply(&self, current: u32, is_dir: bool) -> u32 {
+        match self {
+            Self::Octal {
+                mode,
+                keeps_dir_ids,
+            } if is_dir && *keeps_dir_ids => mode | (current & SET_IDS),
+            Self::Octal { mode, .. } => *mode,
+            Self::Symbolic(clauses) => clauses
+                .iter()
+                .fold(current & 0o7777, |mode, clause| clause.apply(mode, is_dir)),
+        }
+    }
+}
+
+impl ModeClause {
+    fn parse(clause: &str, umask: u32) -> Result<Self, String> {
+        let mut chars = clause.chars().peekable();
+
+        let mut who = 0;
+        while let Some(&c) = chars.peek() {
+            who |= match c {
+                'u' => WHO_USER,
+                'g' => WHO_GROUP,
+                'o' => WHO_OTHER,
+                'a' => WHO_ALL,
+                _ => break,
+            };
+            chars.next();
+        }
+
+        let mut actions: Vec<(char, Vec<char>)> = Vec::new();
+        for c in chars {
+            match c {
+                '+' | '-' | '=' => actions.push((c, Vec::new())),
+                'r' | 'w' | 'x' | 'X' | 's' | 't' => match actions.last_mut() {
+                    Some((_, perms)) => perms.push(c),
+                    None => return Err(format!("Missing operator in mode: {}", clause)),
+                },
+                _ => return Err(format!("Invalid character '{}' in mode: {}", c, clause)),
+            }
+        }
+
+        if actions.is_empty() {
+            return Err(format!("Missing operator in mode: {}", clause));
+        }
+        Ok(Self {
+            who: (who != 0).then_some(who),
+            umask: umask & 0o7777,
+            actions,
+        })
+    }
+
+    fn apply(&self, mut mode: u32, is_dir: bool) -> u32 {
+        let affected = self.who.unwrap_or(WHO_ALL & !self.umask);
+        for (op, perms) in &self.actions {
+            let bits = perms.iter().fold(0, |bits, perm| {
+                bits | match perm {
+                    'r' => 0o444,
+                    'w' => 0o222,
+                    'x' => 0o111,
+                    // Execute only for directories or files that are already executable by someone
+                    'X' if is_dir || mode & 0o111 != 0 => 0o111,
+                    's' => SET_IDS,
+                    't' => 0o1000,
+                    _ => 0,
+                }
+            }) & affected;
+
+            mode = match op {
+                '+' => mode | bits,
+                '-' => mode & !bits,
+                _ => {
+                    // "=" resets every bit of `who`, the umask only limits what is set again
+                    let mut cleared = self.who.unwrap_or(WHO_ALL);
+                    if is_dir && !perms.contains(&'s') {
+                        cleared &= !SET_IDS;
+                    }
+                    (mode & !cleared) | bits
+                }
+            };
+        }
+        mode
+    }
+}
+
+/// Umask of the process, which chmod applies to clauses without "who"
+#[cfg(unix)]
+fn process_umask() -> u32 {
+    // Linux shows it in /proc, reading it elsewhere means briefly changing it for every thread
+    let status = std::fs::read_to_string("/proc/self/status").unwrap_or_default();
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("Umask:"))
+        .and_then(|umask| u32::from_str_radix(umask.trim(), 8).ok())
+        .unwrap_or_else(|| {
+            use nix::sys::stat::{umask, Mode};
+            let current = umask(Mode::from_bits_truncate(0o077));
+            umask(current);
+            current.bits() as u32 // u16 on some platforms
+        })
+}
+
+#[cfg(not(unix))]
+fn process_umask() -> u32 {
+    0
+}
+
+// ------------------- Commands -------------------
+
+/// Changes permissions of the given paths, optionally recursing into folders.
+/// `mode` is octal ("644") or symbolic ("u+x,go-w"). Failures are reported per path.
+#[command]
+pub async fn change_permissions(
+    paths: Vec<String>,
+    mode: String,
+    recursive: bool,
+    dry_run: bool,
+) -> Result<ChangeReport, String> {
+    let spec = ModeSpec::parse(&mode, process_umask())?;
+
+    tokio::task::spawn_blocking(move || {
+        let changes = collect_targets(&paths, recursive)
+            .into_iter()
+            .map(|target| match target {
+                Ok(path) => chmod_path(&path, &spec, dry_run),
+                Err(change) => change,
+            })
+            .collect();
+        ChangeReport::new(dry_run, changes)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {:?}", e))
+}
+
+/// Changes owner and/or group of the given paths, optionally recursing into folders.
+/// Users and groups can be given by name or numeric id. Symlinks are changed themselves.
+#[command]
+pub async fn change_owner(
+    paths: Vec<String>,
+    owner: Option<String>,
+    group: Option<String>,
+    recursive: bool,
+    dry_run: bool,
+) -> Result<ChangeReport, String> {
+    if owner.is_none() && group.is_none() {
+        return Err("Neither owner nor group given".to_string());
+    }
+    let uid = owner.as_deref().map(resolve_user).transpose()?;
+    let gid = group.as_deref().map(resolve_group).transpose()?;
+
+    tokio::task::spawn_blocking(move || {
+        let changes = collect_targets(&paths, recursive)
+            .into_iter()
+            .map(|target| match target {
+                Ok(path) => chown_path(&path, uid, gid, dry_run),
+                Err(change) => change,
+            })
+            .collect();
+        ChangeReport::new(dry_run, changes)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {:?}", e))
+}
+
+/// Expands the selection into the list of paths to change.
+/// Children come before their parents so removing access from a folder doesn't
+/// prevent changing its contents. Unreadable entries are reported as failures.
+fn collect_targets(paths: &[String], recursive: bool) -> Vec<Result<PathBuf, AttributeChange>> {
+    let mut targets = Vec::new();
+
+    for path in paths {
+        if !recursive {
+            targets.push(Ok(decode_path(path)));
+            continue;
+        }
+
+        // Symlinks inside the tree are never followed, like chmod -R / chown -R
+        let path = decode_path(path);
+        for entry in WalkDir::new(&path).contents_first(true) {
+            match entry {
+                Ok(entry) if entry.depth() > 0 && entry.path_is_symlink() => {}
+                Ok(entry) => targets.push(Ok(entry.into_path())),
+                Err(e) => {
+                    let failed_path = encode_path(e.path().unwrap_or(&path));
+                    let error = match e.into_io_error() {
+                        Some(io_error) => describe_io_error(&io_error),
+                        None => "Filesystem loop detected".to_string(),
+                    };
+                    targets.push(Err(AttributeChange {
+                        path: failed_path,
+                        before: String::new(),
+                        after: String::new(),
+                        error: Some(error),
+                    }));
+                }
+            }
+        }
+    }
+
+    targets
+}
+
+// ------------------- Platform implementation -------------------
+
+#[cfg(unix)]
+fn chmod_path(path: &Path, spec: &ModeSpec, dry_run: bool) -> AttributeChange {
+    use crate::details::format_mode;
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+    let mut change = AttributeChange {
+        path: encode_path(path),
+        before: String::new(),
+        after: String::new(),
+        error: None,
+    };
+
+    let metadata = match std::fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            change.error = Some(describe_io_error(&e));
+            return change;
+        }
+    };
+
+    let current = metadata.mode() & 0o7777;
+    let new_mode = spec.apply(current, metadata.is_dir());
+    change.before = format_mode(current);
+    change.after = format_mode(new_mode);
+
+    if dry_run {
+        // Only the owner (or root) may change the mode of a file
+        let euid = nix::unistd::geteuid();
+        if !euid.is_root() && euid.as_raw() != metadata.uid() && new_mode != current {
+            change.error = Some(describe_errno(nix::errno::Errno::EPERM));
+        }
+    } else if new_mode != current {
+        if let Err(e) = std::fs::set_permissions(path, std::fs::Permissions::from_mode(new_mode)) {
+            change.error = Some(describe_io_error(&e));
+        }
+    }
+
+    change
+}
+
+#[cfg(unix)]
+fn chown_path(path: &Path, uid: Option<u32>, gid: Option<u32>, dry_run: bool) -> AttributeChange {
+    use crate::details::{group_name, user_name};
+    use std::os::unix::fs::MetadataExt;
+
+    let owner_label = |uid: u32, gid: u32| {
+        format!(
+            "{}:{}",
+            user_name(uid).unwrap_or_else(|| uid.to_string()),
+            group_name(gid).unwrap_or_else(|| gid.to_string())
+        )
+    };
+
+    let mut change = AttributeChange {
+        path: encode_path(path),
+        before: String::new(),
+        after: String::new(),
+        error: None,
+    };
+
+    let metadata = match std::fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            change.error = Some(describe_io_error(&e));
+            return change;
+        }
+    };
+
+    let new_uid = uid.unwrap_or(metadata.uid());
+    let new_gid = gid.unwrap_or(metadata.gid());
+    change.before = owner_label(metadata.uid(), metadata.gid());
+    change.after = owner_label(new_uid, new_gid);
+
+    if dry_run {
+        // Giving a file away needs root; changing the group needs ownership and membership
+        let euid = nix::unistd::geteuid();
+        let owner_changes = new_uid != metadata.uid();
+        let group_changes = new_gid != metadata.gid();
+        let allowed = euid.is_root()
+            || (!owner_changes
+                && (!group_changes
+                    || (euid.as_raw() == metadata.uid() && is_member_of(new_gid))));
+        if !allowed {
+            change.error = Some(describe_errno(nix::errno::Errno::EPERM));
+        }
+    } else if let Err(e) = std::os::unix::fs::lchown(path, uid, gid) {
+        change.error = Some(describe_io_error(&e));
+    }
+
+    change
+}
+
+#[cfg(unix)]
+fn is_member_of(gid: u32) -> bool {
+    nix::unistd::getegid().as_raw() == gid
+        || nix::unistd::getgroups()
+            .map(|groups| groups.iter().any(|g| g.as_raw() == gid))
+            .unwrap_or(false)
+}
+
+#[cfg(unix)]
+fn resolve_user(user: &str) -> Result<u32, String> {
+    if let Ok(uid) = user.parse::<u32>() {
+        return Ok(uid);
+    }
+    nix::unistd::User::from_name(user)
+        .map_err(|e| format!("Failed to look up user {}: {}", user, describe_errno(e)))?
+        .map(|u| u.uid.as_raw())
+        .ok_or(format!("Unknown user: {}", user))
+}
+
+#[cfg(unix)]
+fn resolve_group(group: &str) -> Result<u32, String> {
+    if let Ok(gid) = group.parse::<u32>() {
+        return Ok(gid);
+    }
+    nix::unistd::Group::from_name(group)
+        .map_err(|e| format!("Failed to look up group {}: {}", group, describe_errno(e)))?
+        .map(|g| g.gid.as_raw())
+        .ok_or(format!("Unknown group: {}", group))
+}
+
+/// Formats errors as "EPERM: Operation not permitted" so the UI can show the errno name
+#[cfg(unix)]
+fn describe_errno(errno: nix::errno::Errno) -> String {
+    format!("{:?}: {}", errno, errno.desc())
+}
+
+#[cfg(unix)]
+fn describe_io_error(e: &std::io::Error) -> String {
+    match e.raw_os_error() {
+        Some(code) => describe_errno(nix::errno::Errno::from_raw(code)),
+        None => e.to_string(),
+    }
+}
+
+#[cfg(not(unix))]
+fn describe_io_error(e: &std::io::Error) -> String {
+    e.to_string()
+}
+
+#[cfg(not(unix))]
+fn unsupported(path: &Path) -> AttributeChange {
+    AttributeChange {
+        path: encode_path(path),
+        before: String::new(),
+        after: String::new(),
+        error: Some("Unix permissions are not supported on this platform".to_string()),
+    }
+}
+
+#[cfg(not(unix))]
+fn chmod_path(path: &Path, _spec: &ModeSpec, _dry_run: bool) -> AttributeChange {
+    unsupported(path)
+}
+
+#[cfg(not(unix))]
+fn chown_path(path: &Path, _uid: Option<u32>, _gid: Option<u32>, _dry_run: bool) -> AttributeChange {
+    unsupported(path)
+}
+
+#[cfg(not(unix))]
+fn resolve_user(user: &str) -> Result<u32, String> {
+    user.parse::<u32>()
+        .map_err(|_| format!("Unknown user: {}", user))
+}
+
+#[cfg(not(unix))]
+fn resolve_group(group: &str) -> Result<u32, String> {
+    group
+        .parse::<u32>()
+        .map_err(|_| format!("Unknown group: {}", group))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ModeSpec;
+
+    const UMASK: u32 = 0o022;
+
+    // (mode, is_dir, current, expected), checked against GNU chmod with umask 022
+    const CASES: &[(&str, bool, u32, u32)] = &[
+        ("755", false, 0o644, 0o755),
+        ("0644", false, 0o4755, 0o644),
+        ("00755", false, 0o644, 0o755),
+        ("0000755", false, 0o644, 0o755),
+        ("7777", false, 0, 0o7777),
+        // Directories keep setuid/setgid unless the mode has five digits or sets them
+        ("755", true, 0o2775, 0o2755),
+        ("00755", true, 0o2775, 0o755),
+        ("4755", true, 0o2775, 0o6755),
+        // Without "who" the umask applies
+        ("+w", false, 0o444, 0o644),
+        ("-w", false, 0o666, 0o466),
+        ("+x", false, 0o644, 0o755),
+        ("=r", false, 0o755, 0o444),
+        ("=rw", false, 0o4777, 0o644),
+        ("+t", true, 0o777, 0o1777),
+        ("a+w", false, 0o444, 0o666),
+        ("a-w", false, 0o666, 0o444),
+        ("u+x,go-w", false, 0o666, 0o744),
+        ("u=rwx,g=rx,o=", false, 0o666, 0o750),
+        ("o=", false, 0o777, 0o770),
+        ("ug+rw-x", false, 0o751, 0o661),
+        ("a=rX", false, 0o644, 0o444),
+        ("a=rX", false, 0o744, 0o555),
+        ("a=rX", true, 0o700, 0o555),
+        ("u+s", false, 0o755, 0o4755),
+        ("g+s", true, 0o755, 0o2755),
+        ("o+s", false, 0o755, 0o755),
+        ("u-s", false, 0o4755, 0o755),
+        ("g=rx", true, 0o2775, 0o2755),
+        ("g=rxs", false, 0o775, 0o2755),
+    ];
+
+    #[test]
+    fn applies_modes_like_chmod() {
+        for &(spec, is_dir, current, expected) in CASES {
+            let mode = ModeSpec::parse(spec, UMASK).unwrap();
+            assert_eq!(
+                mode.apply(current, is_dir),
+                expected,
+                "{} on {:o} (directory: {})",
+                spec,
+                current,
+                is_dir
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_modes() {
+        for spec in ["", " ", "17777", "8", "0o755", "u", "z+x", "u+q", "r+w", "+w,", "u+x,,g+x"] {
+            assert!(ModeSpec::parse(spec, UMASK).is_err(), "{:?}", spec);
+        }
+    }
+
+    #[test]
+    fn explicit_who_ignores_umask() {
+        let mode = ModeSpec::parse("a+rwx", 0o077).unwrap();
+        assert_eq!(mode.apply(0, false), 0o777);
+        let mode = ModeSpec::parse("+rwx", 0o077).unwrap();
+        assert_eq!(mode.apply(0, false), 0o700);
+    }
+}
@@ -0,0 +1,405 @@
+use once_cell::sync::Lazy;
+use std::{
+    fs::{self, File},
+    io::Read,
+    path::Path,
+};
+use syntect::{
+    easy::ScopeRangeIterator,
+    parsing::{ParseState, Scope, ScopeStack, SyntaxReference, SyntaxSet},
+};
+use tauri::command;
+
+use crate::paths::decode_path;
+
+const DEFAULT_PREVIEW_KB: usize = 64;
+const MAX_PREVIEW_KB: usize = 1024;
+
+/// Highlighting stops at the first line longer than this, minified files make the regexes crawl
+const MAX_HIGHLIGHT_LINE: usize = 4096;
+
+static SYNTAXES: Lazy<SyntaxSet> = Lazy::new(SyntaxSet::load_defaults_newlines);
+
+// Checked innermost scope first, so more specific prefixes must come before broader ones
+static TOKEN_SCOPES: Lazy<Vec<(Scope, TokenKind)>> = Lazy::new(|| {
+    [
+        ("comment", TokenKind::Comment),
+        ("string", TokenKind::String),
+        ("constant.numeric", TokenKind::Number),
+        ("constant", TokenKind::Constant),
+        ("keyword.operator", TokenKind::Operator),
+        ("keyword", TokenKind::Keyword),
+        ("storage", TokenKind::Keyword),
+        ("variable.language", TokenKind::Keyword),
+        ("entity.name.function", TokenKind::Function),
+        ("support.function", TokenKind::Function),
+        ("variable.function", TokenKind::Function),
+        ("entity.name.tag", TokenKind::Tag),
+        ("entity.other.attribute-name", TokenKind::Attribute),
+        ("entity.name", TokenKind::Type),
+        ("entity.other.inherited-class", TokenKind::Type),
+        ("support.type", TokenKind::Type),
+        ("support.class", TokenKind::Type),
+        ("markup.heading", TokenKind::Keyword),
+    ]
+    .into_iter()
+    .filter_map(|(scope, kind)| Scope::new(scope).ok().map(|scope| (scope, kind)))
+    .collect()
+});
+
+#[derive(serde::Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TextEncoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+}
+
+#[derive(serde::Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LineEnding {
+    Lf,
+    CrLf,
+    Cr,
+    Mixed,
+    None, // single line without terminator
+}
+
+#[derive(serde::Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenKind {
+    Comment,
+    String,
+    Number,
+    Constant,
+    Keyword,
+    Operator,
+    Function,
+    Type,
+    Tag,
+    Attribute,
+}
+
+/// A highlighted range of one line.
+/// Offsets are in UTF-16 code units so they can be passed to `String.slice` as is.
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct HighlightSpan {
+    pub line: usize, // 0-based
+    pub start: usize,
+    pub end: usize,
+    pub kind: TokenKind,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct FilePreview {
+    pub file_size: u64,
+    pub binary: bool, // content and spans are empty for binary files
+    pub encoding: Option<TextEncoding>,
+    pub has_bom: bool,
+    pub line_ending: LineEnding,
+    pub line_count: usize, // of the previewed part only when truncated
+    pub truncated: bool,
+    pub language: Option<String>, // e.g. "Rust", None for plain text
+    pub content: String,
+    pub spans: Vec<HighlightSpan>,
+}
+
+/// Returns the first `max_kb` KB (64 by default) of a text file for the quick-look panel.
+/// Binary files are not an error, they come back with `binary` set and no content.
+#[command]
+pub async fn preview_file(path: String, max_kb: Option<usize>) -> Result<FilePreview, String> {
+    let limit = max_kb
+        .unwrap_or(DEFAULT_PREVIEW_KB)
+        .clamp(1, MAX_PREVIEW_KB)
+        * 1024;
+    tokio::task::spawn_blocking(move || read_preview(&decode_path(&path), limit))
+        .await
+        .map_err(|e| format!("Task join error: {:?}", e))?
+}
+
+fn read_preview(path: &Path, limit: usize) -> Result<FilePreview, String> {
+    // Checked before opening: opening a FIFO blocks until a writer shows up
+    let metadata = fs::metadata(path)
+        .map_err(|e| format!("Failed to read metadata of {:?}: {:?}", path, e))?;
+    if !metadata.is_file() {
+        return Err(format!("Not a regular file: {:?}", path));
+    }
+    let file_size = metadata.len();
+    let file = File::open(path).map_err(|e| format!("Failed to open {:?}: {:?}", path, e))?;
+
+    let mut bytes = Vec::with_capacity(limit.min(file_size as usize));
+    file.take(limit as u64)
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to read {:?}: {:?}", path, e))?;
+    let truncated = (bytes.len() as u64) < file_size;
+
+    let Some((encoding, has_bom, content)) = decode(&bytes, truncated) else {
+        return Ok(FilePreview {
+            file_size,
+            binary: true,
+            encoding: None,
+            has_bom: false,
+            line_ending: LineEnding::None,
+            line_count: 0,
+            truncated,
+            language: None,
+            content: String::new(),
+            spans: Vec::new(),
+        });
+    };
+
+    let syntax = find_syntax(path, &content);
+    let spans = syntax.map(|s| highlight(&content, s)).unwrap_or_default();
+
+    Ok(FilePreview {
+        file_size,
+        binary: false,
+        encoding: Some(encoding),
+        has_bom,
+        line_ending: line_ending(&content),
+        line_count: lines(&content).count(),
+        truncated,
+        language: syntax.map(|s| s.name.clone()),
+        content,
+        spans,
+    })
+}
+
+// ------------------- Encoding detection -------------------
+
+/// Picks the encoding from the BOM, then UTF-16 zero byte patterns, UTF-8 validity and
+/// finally Latin-1. Returns None when the bytes look binary.
+fn decode(bytes: &[u8], truncated: bool) -> Option<(TextEncoding, bool, String)> {
+    if let Some(rest) = bytes.strip_prefix(b"\xEF\xBB\xBF") {
+        return decode_utf8(rest, truncated).map(|text| (TextEncoding::Utf8, true, text));
+    }
+    if let Some(rest) = bytes.strip_prefix(b"\xFF\xFE") {
+        return decode_utf16(rest, false, truncated)
+            .map(|text| (TextEncoding::Utf16Le, true, text));
+    }
+    if let Some(rest) = bytes.strip_prefix(b"\xFE\xFF") {
+        return decode_utf16(rest, true, truncated).map(|text| (TextEncoding::Utf16Be, true, text));
+    }
+
+    if let Some(big_endian) = guess_utf16(bytes) {
+        let encoding = if big_endian {
+            TextEncoding::Utf16Be
+        } else {
+            TextEncoding::Utf16Le
+        };
+        if let Some(text) = decode_utf16(bytes, big_endian, truncated) {
+            return Some((encoding, false, text));
+        }
+    }
+
+    if bytes.contains(&0) {
+        return None;
+    }
+    if let Some(text) = decode_utf8(bytes, truncated) {
+        return Some((TextEncoding::Utf8, false, text));
+    }
+
+    // Latin-1 maps every byte to the code point of the same value
+    let controls = bytes.iter().filter(|&&b| is_binary_control(b)).count();
+    if controls * 100 > bytes.len() {
+        return None;
+    }
+    Some((
+        TextEncoding::Latin1,
+        false,
+        bytes.iter().map(|&b| b as char).collect(),
+    ))
+}
+
+fn decode_utf8(bytes: &[u8], truncated: bool) -> Option<String> {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Some(text.to_string()),
+        // The read limit can cut a multi-byte character in half
+        Err(e) if truncated && e.error_len().is_none() => {
+            Some(String::from_utf8_lossy(&bytes[..e.valid_up_to()]).into_owned())
+        }
+        Err(_) => None,
+    }
+}
+
+fn decode_utf16(bytes: &[u8], big_endian: bool, truncated: bool) -> Option<String> {
+    let mut units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| {
+            if big_endian {
+                u16::from_be_bytes([pair[0], pair[1]])
+            } else {
+                u16::from_le_bytes([pair[0], pair[1]])
+            }
+        })
+        .collect();
+    if truncated && units.last().is_some_and(|u| (0xD800..0xDC00).contains(u)) {
+        units.pop();
+    }
+
+    let text: String = char::decode_utf16(units.iter().copied())
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect();
+    let suspicious = text
+        .chars()
+        .filter(|&c| c == char::REPLACEMENT_CHARACTER || (c < ' ' && !"\t\n\r\x0c".contains(c)))
+        .count();
+    (suspicious * 100 <= units.len()).then_some(text)
+}
+
+/// BOM-less UTF-16 is recognised by mostly-ASCII text having every other byte zero.
+/// Returns Some(true) for big endian, Some(false) for little endian.
+fn guess_utf16(bytes: &[u8]) -> Option<bool> {
+    let pairs = bytes.len() / 2;
+    if pairs < 2 {
+        return None;
+    }
+    let sample = &bytes[..bytes.len().min(4096) & !1];
+    let even_zeros = sample.iter().step_by(2).filter(|&&b| b == 0).count();
+    let odd_zeros = sample
+        .iter()
+        .skip(1)
+        .step_by(2)
+        .filter(|&&b| b == 0)
+        .count();
+    let half = sample.len() / 2;
+
+    if odd_zeros * 10 > half * 4 && even_zeros * 20 < half {
+        Some(false)
+    } else if even_zeros * 10 > half * 4 && odd_zeros * 20 < half {
+        Some(true)
+    } else {
+        None
+    }
+}
+
+/// C0 controls besides whitespace, form feed and escape (used by ANSI colored logs)
+fn is_binary_control(byte: u8) -> bool {
+    (byte < 0x20 && !b"\t\n\r\x0c\x1b".contains(&byte)) || byte == 0x7f
+}
+
+// ------------------- Lines -------------------
+
+/// Splits on `\n`, `\r\n` and lone `\r`, yielding each line without its terminator
+fn lines(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = Some(text);
+    std::iter::from_fn(move || {
+        let current = rest?;
+        match current.find(['\n', '\r']) {
+            Some(i) => {
+                let skip = if current[i..].starts_with("\r\n") {
+                    2
+                } else {
+                    1
+                };
+                rest = Some(&current[i + skip..]).filter(|r| !r.is_empty());
+                Some(&current[..i])
+            }
+            None => {
+                rest = None;
+                Some(current)
+            }
+        }
+    })
+}
+
+fn line_ending(text: &str) -> LineEnding {
+    let bytes = text.as_bytes();
+    let (mut lf, mut crlf, mut cr) = (0, 0, 0);
+    let mut i = 0;
+    while i < bytes.len() {
+        match (bytes[i], bytes.get(i + 1)) {
+            (b'\r', Some(b'\n')) => {
+                crlf += 1;
+                i += 1;
+            }
+            (b'\r', _) => cr += 1,
+            (b'\n', _) => lf += 1,
+            _ => {}
+        }
+        i += 1;
+    }
+
+    match (lf > 0, crlf > 0, cr > 0) {
+        (false, false, false) => LineEnding::None,
+        (true, false, false) => LineEnding::Lf,
+        (false, true, false) => LineEnding::CrLf,
+        (false, false, true) => LineEnding::Cr,
+        _ => LineEnding::Mixed,
+    }
+}
+
+// ------------------- Highlighting -------------------
+
+/// Chooses a syntax from the extension, then the full file name (`Makefile`),
+/// then the first line (shebangs, mode lines)
+fn find_syntax(path: &Path, content: &str) -> Option<&'static SyntaxReference> {
+    let by_name = |name: Option<&std::ffi::OsStr>| {
+        name.and_then(|n| n.to_str())
+            .and_then(|n| SYNTAXES.find_syntax_by_extension(n))
+    };
+    let syntax = by_name(path.extension())
+        .or_else(|| by_name(path.file_name()))
+        .or_else(|| SYNTAXES.find_syntax_by_first_line(lines(content).next()?))?;
+
+    (syntax.name != SYNTAXES.find_syntax_plain_text().name).then_some(syntax)
+}
+
+fn highlight(content: &str, syntax: &SyntaxReference) -> Vec<HighlightSpan> {
+    let mut spans: Vec<HighlightSpan> = Vec::new();
+    let mut state = ParseState::new(syntax);
+    let mut stack = ScopeStack::new();
+
+    for (line_number, line) in lines(content).enumerate() {
+        if line.len() > MAX_HIGHLIGHT_LINE {
+            break;
+        }
+        let line_len = line.encode_utf16().count();
+        // The bundled syntaxes are compiled for lines ending with a newline
+        let line = format!("{}\n", line);
+        let Ok(ops) = state.parse_line(&line, &SYNTAXES) else {
+            break;
+        };
+
+        let mut offset = 0; // in UTF-16 code units
+        for (range, op) in ScopeRangeIterator::new(&ops, &line) {
+            if stack.apply(op).is_err() {
+                return spans;
+            }
+            let text = &line[range];
+            let start = offset;
+            offset = (offset + text.encode_utf16().count()).min(line_len);
+            if start == offset {
+                continue;
+            }
+            let Some(kind) = token_kind(&stack) else {
+                continue;
+            };
+
+            match spans.last_mut() {
+                Some(last)
+                    if last.line == line_number && last.kind == kind && last.end == start =>
+                {
+                    last.end = offset
+                }
+                _ => spans.push(HighlightSpan {
+                    line: line_number,
+                    start,
+                    end: offset,
+                    kind,
+                }),
+            }
+        }
+    }
+    spans
+}
+
+fn token_kind(stack: &ScopeStack) -> Option<TokenKind> {
+    stack.as_slice().iter().rev().find_map(|&scope| {
+        TOKEN_SCOPES
+            .iter()
+            .find(|(prefix, _)| prefix.is_prefix_of(scope))
+            .map(|&(_, kind)| kind)
+    })
+}
@@ -0,0 +1,418 @@
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+use tauri::{command, AppHandle};
+
+use crate::config;
+use crate::error::ExplorerError;
+use crate::format::Formatter;
+use crate::paths;
+
+/// Version of `recent.json`
+pub const RECENT_VERSION: u32 = 1;
+const RECENT_FILE: &str = "recent.json";
+
+/// Entries kept, the ones with the lowest frecency are dropped first
+const MAX_RECENT: usize = 1000;
+/// A visit counts half as much after this many days
+const HALF_LIFE_DAYS: f64 = 14.0;
+/// Visits are written this long after the first unsaved one, so browsing through
+/// a dozen folders rewrites the file once
+const SAVE_DELAY: Duration = Duration::from_secs(2);
+
+const BOOKMARK_NS: &str = "http://www.freedesktop.org/standards/desktop-bookmarks";
+const MIME_NS: &str = "http://www.freedesktop.org/standards/shared-mime-info";
+
+// Loaded from disk on first use
+static RECENT: Lazy<Mutex<Option<RecentStore>>> = Lazy::new(|| Mutex::new(None));
+
+// Set while a delayed save is waiting to run
+static SAVE_PENDING: AtomicBool = AtomicBool::new(false);
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RecentKind {
+    File,
+    Folder,
+}
+
+/// Usage of one path as stored in `recent.json`
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct RecentEntry {
+    pub path: String, // encoded with `paths::encode_path`
+    pub kind: RecentKind,
+    #[serde(default)]
+    pub visits: u32,
+    #[serde(default)]
+    pub last_visit: Option<i64>, // seconds since the epoch
+    // Copied from `recently-used.xbel` on every import, never added to our own counts
+    #[serde(default)]
+    pub desktop_visits: u32,
+    #[serde(default)]
+    pub desktop_last_visit: Option<i64>,
+}
+
+impl RecentEntry {
+    fn new(path: String, kind: RecentKind) -> Self {
+        Self {
+            path,
+            kind,
+            visits: 0,
+            last_visit: None,
+            desktop_visits: 0,
+            desktop_last_visit: None,
+        }
+    }
+
+    pub fn total_visits(&self) -> u32 {
+        self.visits.saturating_add(self.desktop_visits)
+    }
+
+    pub fn last_used(&self) -> Option<i64> {
+        self.last_visit.max(self.desktop_last_visit)
+    }
+
+    /// Visits weighted by how recent the last one is: frequently and recently used
+    /// paths score highest, paths not touched for months fade out
+    pub fn frecency(&self, now: i64) -> f64 {
+        let Some(last_used) = self.last_used() else {
+            return 0.0;
+        };
+        let age_days = (now - last_used).max(0) as f64 / 86400.0;
+        self.total_visits() as f64 * 0.5f64.powf(age_days / HALF_LIFE_DAYS)
+    }
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct RecentItem {
+    #[serde(flatten)]
+    pub entry: RecentEntry,
+    pub name: String,
+    pub frecency: f64,
+    pub formatted_last_used: String,
+    pub exists: bool,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+struct RecentFile {
+    version: u32,
+    entries: Vec<RecentEntry>,
+}
+
+struct RecentStore {
+    path: PathBuf,
+    file: RecentFile,
+}
+
+impl RecentStore {
+    fn load(app: &AppHandle) -> Result<Self, ExplorerError> {
+        let path = config::config_path(app, RECENT_FILE)?;
+        let file = match config::read_versioned(&path)? {
+            None => RecentFile {
+                version: RECENT_VERSION,
+                entries: Vec::new(),
+            },
+            Some((version, document)) => {
+                config::check_version(&path, version, RECENT_VERSION)?;
+                serde_json::from_value(document).map_err(|e| {
+                    ExplorerError::Unavailable(format!("Invalid {}: {}", RECENT_FILE, e))
+                })?
+            }
+        };
+        Ok(Self { path, file })
+    }
+
+    fn save(&mut self) -> Result<(), ExplorerError> {
+        self.prune(Utc::now().timestamp());
+        self.file.version = RECENT_VERSION;
+        config::write_json(&self.path, &self.file)
+    }
+
+    /// Drops entries nothing refers to anymore, then the least used ones past `MAX_RECENT`
+    fn prune(&mut self, now: i64) {
+        let entries = &mut self.file.entries;
+        entries.retain(|e| e.total_visits() > 0);
+        if entries.len() > MAX_RECENT {
+            entries.sort_by(|a, b| b.frecency(now).total_cmp(&a.frecency(now)));
+            entries.truncate(MAX_RECENT);
+        }
+    }
+
+    fn entry(&mut self, path: &str, kind: RecentKind) -> &mut RecentEntry {
+        let entries = &mut self.file.entries;
+        let index = match entries.iter().position(|e| e.path == path) {
+            Some(index) => index,
+            None => {
+                entries.push(RecentEntry::new(path.to_string(), kind));
+                entries.len() - 1
+            }
+        };
+        &mut entries[index]
+    }
+}
+
+fn to_item(entry: &RecentEntry, formatter: &Formatter) -> RecentItem {
+    let path = paths::decode_path(&entry.path);
+    RecentItem {
+        name: paths::file_name(&path),
+        frecency: entry.frecency(formatter.now.timestamp()),
+        formatted_last_used: formatter.date(entry.last_used()),
+        exists: fs::symlink_metadata(&path).is_ok(),
+        entry: entry.clone(),
+    }
+}
+
+/// Runs `f` on the history, loading it on first use
+fn with_store<T>(
+    app: &AppHandle,
+    f: impl FnOnce(&mut RecentStore) -> Result<T, ExplorerError>,
+) -> Result<T, ExplorerError> {
+    let mut store = RECENT.lock().unwrap();
+    if store.is_none() {
+        *store = Some(RecentStore::load(app)?);
+    }
+    match store.as_mut() {
+        Some(store) => f(store),
+        None => Err(ExplorerError::Internal(
+            "Recent files not loaded".to_string(),
+        )),
+    }
+}
+
+/// Frecency of every known path, keyed by encoded path. Empty if the history can't be loaded,
+/// so search still works without it.
+pub fn frecency_scores(app: &AppHandle) -> HashMap<String, f64> {
+    let now = Utc::now().timestamp();
+    with_store(app, |store| {
+        Ok(store
+            .file
+            .entries
+            .iter()
+            .map(|e| (e.path.clone(), e.frecency(now)))
+            .collect())
+    })
+    .unwrap_or_default()
+}
+
+/// Loads the history and refreshes the desktop-wide recent files in the background,
+/// a large `recently-used.xbel` shouldn't delay startup
+pub fn init(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        if let Err(e) = import_desktop(&app) {
+            config::report(
+                &app,
+                RECENT_FILE,
+                format!("Failed to import recent files: {}", e),
+            );
+        }
+    });
+}
+
+/// Writes the history once `SAVE_DELAY` has passed, unless a save is already waiting
+fn schedule_save(app: &AppHandle) {
+    if SAVE_PENDING.swap(true, Ordering::AcqRel) {
+        return;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(SAVE_DELAY);
+        flush(&app);
+    });
+}
+
+/// Writes visits that are still waiting for their delayed save, called on exit
+pub fn flush(app: &AppHandle) {
+    // Cleared first, so a visit recorded while saving schedules another save
+    if !SAVE_PENDING.swap(false, Ordering::AcqRel) {
+        return;
+    }
+    if let Some(store) = RECENT.lock().unwrap().as_mut() {
+        if let Err(e) = store.save() {
+            config::report(
+                app,
+                RECENT_FILE,
+                format!("Failed to save recent files: {}", e),
+            );
+        }
+    }
+}
+
+/// Records that the user opened a file or navigated to a folder
+#[command]
+pub async fn record_visit(app: AppHandle, path: String) -> Result<RecentItem, ExplorerError> {
+    tokio::task::spawn_blocking(move || {
+        let target = paths::decode_path(&path);
+        let metadata = fs::metadata(&target).map_err(|e| ExplorerError::io(e, &target))?;
+        let kind = if metadata.is_dir() {
+            RecentKind::Folder
+        } else {
+            RecentKind::File
+        };
+        let path = paths::encode_path(&target);
+
+        let entry = with_store(&app, |store| {
+            let entry = store.entry(&path, kind);
+            entry.kind = kind;
+            entry.visits = entry.visits.saturating_add(1);
+            entry.last_visit = Some(Utc::now().timestamp());
+            Ok(entry.clone())
+        })?;
+        schedule_save(&app);
+        Ok(to_item(&entry, &Formatter::current()))
+    })
+    .await?
+}
+
+/// Most recently used paths first. `kind` keeps only files or only folders.
+#[command]
+pub async fn list_recent(
+    app: AppHandle,
+    kind: Option<RecentKind>,
+    limit: Option<usize>,
+) -> Result<Vec<RecentItem>, ExplorerError> {
+    tokio::task::spawn_blocking(move || {
+        let mut entries = with_store(&app, |store| {
+            Ok(store
+                .file
+                .entries
+                .iter()
+                .filter(|e| kind.is_none_or(|k| e.kind == k))
+                .cloned()
+                .collect::<Vec<_>>())
+        })?;
+        entries.sort_by_key(|e| std::cmp::Reverse(e.last_used()));
+
+        // Checking which paths still exist happens outside the lock
+        let formatter = Formatter::current();
+        Ok(entries
+            .iter()
+            .take(limit.unwrap_or(usize::MAX))
+            .map(|e| to_item(e, &formatter))
+            .collect())
+    })
+    .await?
+}
+
+/// Forgets a path. Returns false if it wasn't in the history.
+/// Paths that come from the desktop history show up again on the next import.
+#[command]
+pub async fn remove_recent(app: AppHandle, path: String) -> Result<bool, ExplorerError> {
+    // Same form as `record_visit` stores
+    let path = paths::encode_path(&paths::decode_path(&path));
+    tokio::task::spawn_blocking(move || {
+        with_store(&app, |store| {
+            let before = store.file.entries.len();
+            store.file.entries.retain(|e| e.path != path);
+            if store.file.entries.len() == before {
+                return Ok(false);
+            }
+            store.save()?;
+            Ok(true)
+        })
+    })
+    .await?
+}
+
+#[command]
+pub async fn clear_recent(app: AppHandle) -> Result<(), ExplorerError> {
+    tokio::task::spawn_blocking(move || {
+        with_store(&app, |store| {
+            store.file.entries.clear();
+            store.save()
+        })
+    })
+    .await?
+}
+
+/// Re-reads `recently-used.xbel` and returns how many of its entries were imported
+#[command]
+pub async fn import_desktop_recent(app: AppHandle) -> Result<usize, ExplorerError> {
+    tokio::task::spawn_blocking(move || import_desktop(&app)).await?
+}
+
+fn import_desktop(app: &AppHandle) -> Result<usize, ExplorerError> {
+    let imported = match dirs_next::data_dir() {
+        Some(dir) => read_xbel(&dir.join("recently-used.xbel"))?,
+        None => Vec::new(),
+    };
+
+    with_store(app, |store| {
+        // The desktop counts are replaced, so importing twice never counts a visit twice
+        for entry in &mut store.file.entries {
+            entry.desktop_visits = 0;
+            entry.desktop_last_visit = None;
+        }
+        for (path, kind, visits, last_visit) in &imported {
+            let entry = store.entry(path, *kind);
+            entry.desktop_visits = *visits;
+            entry.desktop_last_visit = *last_visit;
+        }
+        store.save()?;
+        Ok(imported.len())
+    })
+}
+
+/// (encoded path, kind, visit count, last visit)
+type DesktopEntry = (String, RecentKind, u32, Option<i64>);
+
+/// Local paths from a freedesktop.org recent files list. A missing file is an empty list.
+fn read_xbel(path: &Path) -> Result<Vec<DesktopEntry>, ExplorerError> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(ExplorerError::io(e, path)),
+    };
+    let document = roxmltree::Document::parse(&text)
+        .map_err(|e| ExplorerError::Unavailable(format!("Invalid {}: {}", path.display(), e)))?;
+
+    let timestamp = |value: Option<&str>| {
+        value
+            .and_then(|v| DateTime::parse_from_rfc3339(v).ok())
+            .map(|dt| dt.timestamp())
+    };
+
+    let mut entries = Vec::new();
+    for bookmark in document
+        .root_element()
+        .children()
+        .filter(|n| n.has_tag_name("bookmark"))
+    {
+        // Only local files, other URIs (sftp://, trash://, ...) aren't browsable here
+        let Some(local) = bookmark
+            .attribute("href")
+            .and_then(|href| href.strip_prefix("file://"))
+        else {
+            continue;
+        };
+        let file = paths::bytes_to_path(paths::percent_decode(local));
+
+        let mut visits = 0u32;
+        let mut kind = RecentKind::File;
+        for node in bookmark.descendants() {
+            if node.has_tag_name((BOOKMARK_NS, "application")) {
+                let count = node.attribute("count").and_then(|c| c.parse().ok());
+                visits = visits.saturating_add(count.unwrap_or(1));
+            } else if node.has_tag_name((MIME_NS, "mime-type"))
+                && node.attribute("type") == Some("inode/directory")
+            {
+                kind = RecentKind::Folder;
+            }
+        }
+
+        let last_visit = timestamp(bookmark.attribute("visited"))
+            .max(timestamp(bookmark.attribute("modified")))
+            .max(timestamp(bookmark.attribute("added")));
+        entries.push((paths::encode_path(&file), kind, visits.max(1), last_visit));
+    }
+    Ok(entries)
+}
@@ -0,0 +1,282 @@
+use once_cell::sync::Lazy;
+use rayon::prelude::*;
+use std::{
+    cmp::Ordering as CmpOrdering,
+    collections::BTreeMap,
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+use tauri::command;
+
+use crate::directories::{ensure_directory, ListingRequest};
+use crate::error::{ExplorerError, SkipSummary};
+use crate::filetype;
+use crate::format::Formatter;
+use crate::models::{epoch_seconds, FileInfo, FileKind};
+use crate::paths;
+use crate::settings;
+
+/// Sessions kept at once, opening one more closes the oldest
+const MAX_SESSIONS: usize = 16;
+/// Largest window returned by a single `fetch_listing_range` call
+const MAX_PAGE_LEN: usize = 1000;
+
+static NEXT_SESSION_ID: AtomicU64 = AtomicU64::new(1);
+
+// Ids only grow, so the first key is always the oldest session
+static SESSIONS: Lazy<Mutex<BTreeMap<u64, Arc<ListingSession>>>> =
+    Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortField {
+    #[default]
+    Name,
+    Size,
+    Modified,
+    Type, // extension, then name
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListingSort {
+    #[serde(default)]
+    pub field: SortField,
+    #[serde(default)]
+    pub descending: bool,
+    #[serde(default = "directories_first_default")]
+    pub directories_first: bool, // kept on top in both directions
+}
+
+fn directories_first_default() -> bool {
+    true
+}
+
+impl Default for ListingSort {
+    fn default() -> Self {
+        Self {
+            field: SortField::Name,
+            descending: false,
+            directories_first: true,
+        }
+    }
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct OpenedListing {
+    pub session_id: u64,
+    pub directory: String,
+    pub total: usize,
+    pub skipped: SkipSummary,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct ListingPage {
+    pub session_id: u64,
+    pub start: usize,
+    pub total: usize,
+    pub files: Vec<FileInfo>,
+}
+
+/// Sorted snapshot of a folder. Only what sorting needs is kept per entry,
+/// full `FileInfo`s are built for the requested window.
+struct ListingSession {
+    directory: PathBuf,
+    entries: Vec<SessionEntry>,
+}
+
+struct SessionEntry {
+    name: OsString,
+    sort_name: String, // lowercase display name
+    kind: FileKind,
+    size: u64,
+    modified: Option<i64>,
+}
+
+impl SessionEntry {
+    fn extension(&self) -> &str {
+        match self.sort_name.rsplit_once('.') {
+            Some((stem, extension)) if !stem.is_empty() => extension,
+            _ => "",
+        }
+    }
+}
+
+/// Reads and sorts a folder once, then serves it page by page through `fetch_listing_range`.
+/// Directory sizes are not computed recursively here, that would defeat the purpose
+/// on folders with hundreds of thousands of entries.
+#[command]
+pub async fn open_listing(
+    path: String,
+    sort: Option<ListingSort>,
+    request_id: Option<String>,
+) -> Result<OpenedListing, ExplorerError> {
+    let directory = paths::decode_path(&path);
+    let sort = sort.unwrap_or_else(|| settings::current().default_sort);
+    let request = ListingRequest::register(request_id);
+
+    tokio::task::spawn_blocking(move || {
+        let (entries, skipped) = read_entries(&directory, sort, &request)?;
+        let session_id = NEXT_SESSION_ID.fetch_add(1, Ordering::Relaxed);
+        let opened = OpenedListing {
+            session_id,
+            directory: paths::encode_path(&directory),
+            total: entries.len(),
+            skipped,
+        };
+
+        let mut sessions = SESSIONS.lock().unwrap();
+        sessions.insert(session_id, Arc::new(ListingSession { directory, entries }));
+        while sessions.len() > MAX_SESSIONS {
+            sessions.pop_first();
+        }
+        Ok(opened)
+    })
+    .await?
+}
+
+/// Returns up to `count` entries starting at `start` in the session's sort order.
+/// Entries deleted since the session was opened come back with their `error` set.
+#[command]
+pub async fn fetch_listing_range(
+    session_id: u64,
+    start: usize,
+    count: usize,
+) -> Result<ListingPage, ExplorerError> {
+    let session = SESSIONS
+        .lock()
+        .unwrap()
+        .get(&session_id)
+        .cloned()
+        .ok_or(ExplorerError::SessionClosed)?;
+
+    tokio::task::spawn_blocking(move || {
+        let total = session.entries.len();
+        let start = start.min(total);
+        let end = start.saturating_add(count.min(MAX_PAGE_LEN)).min(total);
+        let formatter = Formatter::current();
+        let files = session.entries[start..end]
+            .iter()
+            .map(|entry| file_info(&session.directory, entry, &formatter))
+            .collect();
+
+        ListingPage {
+            session_id,
+            start,
+            total,
+            files,
+        }
+    })
+    .await
+    .map_err(ExplorerError::from)
+}
+
+/// Releases a session. Returns false if it was already closed or evicted.
+#[command]
+pub fn close_listing(session_id: u64) -> bool {
+    SESSIONS.lock().unwrap().remove(&session_id).is_some()
+}
+
+fn read_entries(
+    directory: &Path,
+    sort: ListingSort,
+    request: &ListingRequest,
+) -> Result<(Vec<SessionEntry>, SkipSummary), ExplorerError> {
+    ensure_directory(directory)?;
+
+    let show_hidden = settings::current().show_hidden_files;
+    let mut entries = Vec::new();
+    let mut skipped = SkipSummary::default();
+    for entry in fs::read_dir(directory).map_err(|e| ExplorerError::io(e, directory))? {
+        request.check_cancelled()?;
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                // Same as `list_folder`: keep what was read before the folder failed
+                skipped.add_io(directory, &e);
+                break;
+            }
+        };
+
+        let name = entry.file_name();
+        let metadata = entry.metadata();
+        if !show_hidden && paths::is_hidden(&name, metadata.as_ref().ok()) {
+            continue;
+        }
+        let sort_name = paths::display_name(&name).to_lowercase();
+        // Unreadable entries still get a row, fetching them reports the error
+        let (kind, size, modified) = match metadata {
+            Ok(metadata) => (
+                FileKind::from_metadata(&metadata),
+                metadata.len(),
+                metadata.modified().ok().map(epoch_seconds),
+            ),
+            Err(_) => (FileKind::Unknown, 0, None),
+        };
+        entries.push(SessionEntry {
+            name,
+            sort_name,
+            kind,
+            size,
+            modified,
+        });
+    }
+
+    entries.par_sort_unstable_by(|a, b| compare_entries(a, b, sort));
+    request.check_cancelled()?;
+    Ok((entries, skipped))
+}
+
+fn compare_entries(a: &SessionEntry, b: &SessionEntry, sort: ListingSort) -> CmpOrdering {
+    if sort.directories_first {
+        let is_dir = |e: &SessionEntry| e.kind == FileKind::Directory;
+        let order = is_dir(b).cmp(&is_dir(a));
+        if order != CmpOrdering::Equal {
+            return order;
+        }
+    }
+
+    let order = match sort.field {
+        SortField::Name => CmpOrdering::Equal,
+        SortField::Size => a.size.cmp(&b.size),
+        SortField::Modified => a.modified.cmp(&b.modified),
+        SortField::Type => a.extension().cmp(b.extension()),
+    }
+    .then_with(|| a.sort_name.cmp(&b.sort_name))
+    .then_with(|| a.name.cmp(&b.name));
+
+    if sort.descending {
+        order.reverse()
+    } else {
+        order
+    }
+}
+
+/// Type detection is by extension only, a page may hold a thousand files
+fn file_info(directory: &Path, entry: &SessionEntry, formatter: &Formatter) -> FileInfo {
+    let path = directory.join(&entry.name);
+    let file_name = paths::display_name(&entry.name);
+    let mut info = match fs::symlink_metadata(&path) {
+        Ok(metadata) => {
+            let mut info = FileInfo::from_metadata(file_name, paths::encode_path(&path), &metadata);
+            if metadata.is_file() {
+                if let Some(detected) = filetype::detect_by_extension(&path) {
+                    info.mime_type = Some(detected.mime_type);
+                    info.category = Some(detected.category);
+                }
+            }
+            info
+        }
+        Err(e) => FileInfo::unreadable(
+            file_name,
+            paths::encode_path(&path),
+            ExplorerError::io(e, &path),
+        ),
+    };
+    info.format(formatter);
+    info
+}
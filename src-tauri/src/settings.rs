@@ -0,0 +1,386 @@
+use arc_swap::ArcSwap;
+use chrono::format::{Item, StrftimeItems};
+use once_cell::sync::Lazy;
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use tauri::{command, AppHandle, Emitter};
+
+use crate::config;
+use crate::error::ExplorerError;
+use crate::paths;
+use crate::sessions::ListingSort;
+
+/// Version of `settings.json`
+pub const SETTINGS_VERSION: u32 = 1;
+const SETTINGS_FILE: &str = "settings.json";
+
+const MAX_SIZE_PRECISION: u8 = 3;
+
+/// Event emitted with the new settings and the names of the fields that changed
+pub const SETTINGS_CHANGED_EVENT: &str = "settings-changed";
+
+// Read by listings and the indexer without locking, replaced as a whole on update
+static SETTINGS: Lazy<ArcSwap<Settings>> = Lazy::new(|| ArcSwap::from_pointee(Settings::default()));
+
+// Where the file was loaded from, None if loading failed. Also serializes updates.
+static SETTINGS_PATH: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SizeUnits {
+    Si, // kB, MB: powers of 1000
+    #[default]
+    Iec, // KiB, MiB: powers of 1024
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(tag = "style", content = "pattern", rename_all = "snake_case")]
+pub enum DateFormat {
+    #[default]
+    Local, // local time zone, e.g. "2024-05-03 14:07"
+    Relative,       // "3 hours ago", falling back to Local for old dates
+    Iso,            // RFC 3339 in UTC
+    Custom(String), // strftime pattern, e.g. "%d/%m/%Y"
+}
+
+/// Every field has a default, so older files and partial files load fine
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    pub show_hidden_files: bool,
+    pub default_sort: ListingSort,
+    pub index_roots: Vec<String>, // absolute folders to index, empty indexes every disk
+    // Absolute folders, or folder names like "node_modules" excluded wherever they appear
+    pub index_exclusions: Vec<String>,
+    pub size_units: SizeUnits,
+    pub size_precision: u8,     // decimals shown for sizes above one kB/KiB
+    pub locale: Option<String>, // number separators, None follows the system
+    pub date_format: DateFormat,
+    pub confirm_on_delete: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            show_hidden_files: true,
+            default_sort: ListingSort::default(),
+            index_roots: Vec::new(),
+            index_exclusions: Vec::new(),
+            size_units: SizeUnits::default(),
+            size_precision: 1,
+            locale: None,
+            date_format: DateFormat::default(),
+            confirm_on_delete: true,
+        }
+    }
+}
+
+impl Settings {
+    pub fn validate(&self) -> Result<(), ExplorerError> {
+        let invalid = |message: String| Err(ExplorerError::InvalidArgument(message));
+
+        let mut roots = HashSet::new();
+        for root in &self.index_roots {
+            if !paths::decode_path(root).is_absolute() {
+                return invalid(format!("Index root must be an absolute path: {}", root));
+            }
+            if !roots.insert(root) {
+                return invalid(format!("Index root listed twice: {}", root));
+            }
+        }
+        if self.index_exclusions.iter().any(|e| e.trim().is_empty()) {
+            return invalid("Index exclusions can't be empty".to_string());
+        }
+        if self.size_precision > MAX_SIZE_PRECISION {
+            return invalid(format!(
+                "Size precision must be at most {}",
+                MAX_SIZE_PRECISION
+            ));
+        }
+        if let Some(locale) = &self.locale {
+            let is_tag = |c: char| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '@');
+            if locale.is_empty() || !locale.chars().all(is_tag) {
+                return invalid(format!("Invalid locale: {:?}", locale));
+            }
+        }
+        if let DateFormat::Custom(pattern) = &self.date_format {
+            let has_error = StrftimeItems::new(pattern).any(|item| matches!(item, Item::Error));
+            if pattern.is_empty() || has_error {
+                return invalid(format!("Invalid date format: {:?}", pattern));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the indexer should skip `path` and everything below it
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        self.index_exclusions.iter().any(|exclusion| {
+            let excluded = paths::decode_path(exclusion);
+            if excluded.is_absolute() {
+                path.starts_with(&excluded)
+            } else {
+                path.file_name() == Some(excluded.as_os_str())
+            }
+        })
+    }
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct SettingsChanged {
+    pub settings: Settings,
+    pub changed: Vec<String>,
+}
+
+/// Current settings, defaults until `init` has loaded the file
+pub fn current() -> Arc<Settings> {
+    SETTINGS.load_full()
+}
+
+/// Upgrades the fields of a stored document by one version, e.g. renames a field or
+/// converts its value to a new type
+type Migration = fn(&mut serde_json::Map<String, serde_json::Value>);
+
+/// `MIGRATIONS[n]` upgrades version `n` to `n + 1`. Bumping `SETTINGS_VERSION` without
+/// adding a step doesn't compile.
+const MIGRATIONS: [Migration; SETTINGS_VERSION as usize] = [
+    // Hand-written files without a version: the fields are already the version 1 ones
+    |_| {},
+];
+
+/// Upgrades a document of an older version in place, one version at a time
+fn migrate(version: u32, document: &mut serde_json::Value) {
+    apply_migrations(&MIGRATIONS, version, document);
+}
+
+fn apply_migrations(migrations: &[Migration], version: u32, document: &mut serde_json::Value) {
+    if let serde_json::Value::Object(fields) = document {
+        for migration in migrations.iter().skip(version as usize) {
+            migration(fields);
+        }
+    }
+    document["version"] = (migrations.len() as u32).into();
+}
+
+/// Builds settings from a stored document one field at a time, so a single invalid
+/// value falls back to its default instead of discarding the whole file.
+/// Returns the names of the fields that were ignored.
+fn from_document(document: serde_json::Value) -> (Settings, Vec<String>) {
+    let mut settings = Settings::default();
+    let mut ignored = Vec::new();
+    let serde_json::Value::Object(fields) = document else {
+        return (settings, vec!["<document>".to_string()]);
+    };
+    for (key, value) in fields {
+        if key == "version" {
+            continue;
+        }
+        match with_field(&settings, &key, value) {
+            Ok(updated) => settings = updated,
+            Err(_) => ignored.push(key),
+        }
+    }
+    (settings, ignored)
+}
+
+/// `settings` with one field replaced, if the result is valid
+fn with_field(
+    settings: &Settings,
+    key: &str,
+    value: serde_json::Value,
+) -> Result<Settings, ExplorerError> {
+    let mut document = serde_json::to_value(settings)
+        .map_err(|e| ExplorerError::Internal(format!("Failed to serialize settings: {}", e)))?;
+    let Some(field) = document.get_mut(key) else {
+        return Err(ExplorerError::InvalidArgument(format!(
+            "Unknown setting: {}",
+            key
+        )));
+    };
+    *field = value;
+    let settings: Settings = serde_json::from_value(document)
+        .map_err(|e| ExplorerError::InvalidArgument(format!("Invalid {}: {}", key, e)))?;
+    settings.validate()?;
+    Ok(settings)
+}
+
+fn load(app: &AppHandle) -> Result<Settings, ExplorerError> {
+    let path = config::config_path(app, SETTINGS_FILE)?;
+    let settings = match config::read_versioned(&path)? {
+        None => Settings::default(),
+        Some((version, mut document)) => {
+            // The only case where the file is left alone for the whole session
+            config::check_version(&path, version, SETTINGS_VERSION)?;
+            migrate(version, &mut document);
+            let (settings, ignored) = from_document(document);
+            if !ignored.is_empty() {
+                // Kept in the file until the next update rewrites it
+                config::report(
+                    app,
+                    SETTINGS_FILE,
+                    format!(
+                        "Invalid settings replaced by their defaults: {}",
+                        ignored.join(", ")
+                    ),
+                );
+            }
+            if version < SETTINGS_VERSION {
+                save(&path, &settings)?;
+            }
+            settings
+        }
+    };
+    *SETTINGS_PATH.lock().unwrap() = Some(path);
+    Ok(settings)
+}
+
+fn save(path: &Path, settings: &Settings) -> Result<(), ExplorerError> {
+    let mut document = serde_json::to_value(settings)
+        .map_err(|e| ExplorerError::Internal(format!("Failed to serialize settings: {}", e)))?;
+    document["version"] = SETTINGS_VERSION.into();
+    config::write_json(path, &document)
+}
+
+/// Loads the settings at startup. Invalid fields are reported and replaced by their
+/// defaults, a file written by a newer version is ignored and never overwritten.
+pub fn init(app: &AppHandle) {
+    match load(app) {
+        Ok(settings) => SETTINGS.store(Arc::new(settings)),
+        Err(e) => config::report(
+            app,
+            SETTINGS_FILE,
+            format!("Failed to load settings: {}", e),
+        ),
+    }
+}
+
+#[command]
+pub fn get_settings() -> Settings {
+    current().as_ref().clone()
+}
+
+/// Applies the given fields, e.g. `{ "show_hidden_files": true }`, and returns the new
+/// settings. Unknown fields and invalid values are rejected without changing anything.
+#[command]
+pub fn update_settings(
+    app: AppHandle,
+    changes: serde_json::Map<String, serde_json::Value>,
+) -> Result<Settings, ExplorerError> {
+    let path = SETTINGS_PATH.lock().unwrap();
+    let mut settings = current().as_ref().clone();
+    let mut changed = Vec::new();
+    for (key, value) in changes {
+        let updated = with_field(&settings, &key, value)?;
+        if updated != settings {
+            settings = updated;
+            changed.push(key);
+        }
+    }
+    if changed.is_empty() {
+        return Ok(settings);
+    }
+
+    // Never overwrite a file from a newer version, or one that couldn't be read
+    let Some(file) = path.as_ref() else {
+        return Err(ExplorerError::Unavailable(format!(
+            "{} could not be loaded, changes can't be saved",
+            SETTINGS_FILE
+        )));
+    };
+    save(file, &settings)?;
+    SETTINGS.store(Arc::new(settings.clone()));
+
+    let _ = app.emit(
+        SETTINGS_CHANGED_EVENT,
+        SettingsChanged {
+            settings: settings.clone(),
+            changed,
+        },
+    );
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // Fixture for a made up version 2, which renamed `show_hidden` to `show_hidden_files`
+    // and stored `size_precision` as a number instead of a string. A real schema change
+    // adds a step like this one to `MIGRATIONS`.
+    fn upgrade_to_2(fields: &mut serde_json::Map<String, serde_json::Value>) {
+        if let Some(value) = fields.remove("show_hidden") {
+            fields.insert("show_hidden_files".to_string(), value);
+        }
+        let precision = fields
+            .get("size_precision")
+            .and_then(|value| value.as_str())
+            .and_then(|value| value.parse::<u8>().ok());
+        if let Some(precision) = precision {
+            fields.insert("size_precision".to_string(), precision.into());
+        }
+    }
+
+    const FIXTURE_MIGRATIONS: [Migration; 2] = [MIGRATIONS[0], upgrade_to_2];
+
+    #[test]
+    fn migrates_renamed_and_retyped_fields() {
+        let mut document = json!({ "version": 1, "show_hidden": false, "size_precision": "2" });
+        apply_migrations(&FIXTURE_MIGRATIONS, 1, &mut document);
+        assert_eq!(
+            document,
+            json!({ "version": 2, "show_hidden_files": false, "size_precision": 2 })
+        );
+
+        let (settings, ignored) = from_document(document);
+        assert!(ignored.is_empty(), "{:?}", ignored);
+        assert!(!settings.show_hidden_files);
+        assert_eq!(settings.size_precision, 2);
+    }
+
+    #[test]
+    fn runs_every_step_from_the_stored_version() {
+        let mut unversioned = json!({ "show_hidden": false });
+        apply_migrations(&FIXTURE_MIGRATIONS, 0, &mut unversioned);
+        assert_eq!(
+            unversioned,
+            json!({ "version": 2, "show_hidden_files": false })
+        );
+
+        // Already at the latest version: `show_hidden` is an unknown field now, not renamed
+        let mut current = json!({ "version": 2, "show_hidden": false });
+        apply_migrations(&FIXTURE_MIGRATIONS, 2, &mut current);
+        assert_eq!(current, json!({ "version": 2, "show_hidden": false }));
+    }
+
+    #[test]
+    fn version_0_files_keep_their_fields() {
+        let mut document = json!({ "show_hidden_files": false, "size_units": "si" });
+        migrate(0, &mut document);
+        assert_eq!(document["version"], json!(SETTINGS_VERSION));
+
+        let (settings, ignored) = from_document(document);
+        assert!(ignored.is_empty(), "{:?}", ignored);
+        assert!(!settings.show_hidden_files);
+        assert_eq!(settings.size_units, SizeUnits::Si);
+    }
+
+    #[test]
+    fn invalid_fields_fall_back_to_their_default() {
+        let document = json!({
+            "version": SETTINGS_VERSION,
+            "show_hidden_files": false,
+            "size_precision": 9,
+            "date_format": { "style": "custom", "pattern": "%Q" },
+            "unknown": true,
+        });
+        let (settings, mut ignored) = from_document(document);
+        ignored.sort();
+        assert_eq!(ignored, ["date_format", "size_precision", "unknown"]);
+        assert!(!settings.show_hidden_files);
+        assert_eq!(settings.size_precision, Settings::default().size_precision);
+        assert_eq!(settings.date_format, DateFormat::default());
+    }
+}
@@ -0,0 +1,374 @@
+use md5::{Digest, Md5};
+use once_cell::sync::Lazy;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fs::{self, File},
+    io::{self, BufReader, BufWriter},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Mutex,
+    },
+    thread,
+};
+use tauri::{
+    command,
+    http::{header::CONTENT_TYPE, Request, Response, StatusCode},
+    Runtime, UriSchemeContext, UriSchemeResponder,
+};
+
+use crate::models::epoch_seconds;
+use crate::paths::{bytes_to_path, decode_path, path_to_bytes, percent_decode};
+
+/// Custom protocol serving thumbnails to the webview:
+/// `thumbnail://localhost/<size>/<percent-encoded absolute path>`
+/// (`http://thumbnail.localhost/...` on Windows).
+pub const THUMBNAIL_SCHEME: &str = "thumbnail";
+
+/// Directory used for failure markers, as required by the thumbnail spec
+const FAIL_DIR: &str = "fail/file-explorer";
+
+/// Paths kept waiting for a prefetch, the rest of a longer list is left to the protocol
+const MAX_PREFETCH: usize = 1000;
+
+// Makes temporary file names unique when two threads write the same thumbnail
+static NEXT_TEMP_ID: AtomicU64 = AtomicU64::new(0);
+
+// Decoding is CPU heavy, keep it off the IPC and async runtime threads
+static THUMBNAIL_POOL: Lazy<rayon::ThreadPool> = Lazy::new(|| {
+    let threads = std::thread::available_parallelism()
+        .map(|n| n.get().min(4))
+        .unwrap_or(2);
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .thread_name(|i| format!("thumbnail-{}", i))
+        .build()
+        .expect("failed to create thumbnail thread pool")
+});
+
+// Paths waiting to be prefetched, worked through by a single thread outside the pool
+// so thumbnails the view requests never wait behind a long prefetch
+static PREFETCH_QUEUE: Lazy<Mutex<VecDeque<(PathBuf, ThumbnailSize)>>> =
+    Lazy::new(|| Mutex::new(VecDeque::new()));
+static PREFETCH_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Sizes defined by the freedesktop.org thumbnail managing standard
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThumbnailSize {
+    Normal,
+    Large,
+    XLarge,
+    XxLarge,
+}
+
+impl ThumbnailSize {
+    pub fn pixels(self) -> u32 {
+        match self {
+            Self::Normal => 128,
+            Self::Large => 256,
+            Self::XLarge => 512,
+            Self::XxLarge => 1024,
+        }
+    }
+
+    pub fn dir_name(self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::Large => "large",
+            Self::XLarge => "x-large",
+            Self::XxLarge => "xx-large",
+        }
+    }
+
+    pub fn from_dir_name(name: &str) -> Option<Self> {
+        [Self::Normal, Self::Large, Self::XLarge, Self::XxLarge]
+            .into_iter()
+            .find(|size| size.dir_name() == name)
+    }
+}
+
+/// Generates missing thumbnails in the background so scrolling the Pictures view is instant.
+/// Replaces the paths still waiting from the previous call, they belong to a view the
+/// user has left. Only the first `MAX_PREFETCH` distinct paths are kept.
+#[command]
+pub fn prefetch_thumbnails(paths: Vec<String>, size: ThumbnailSize) {
+    let mut seen = HashSet::new();
+    let mut queue = PREFETCH_QUEUE.lock().unwrap();
+    queue.clear();
+    queue.extend(
+        paths
+            .iter()
+            .filter(|path| seen.insert(path.as_str()))
+            .take(MAX_PREFETCH)
+            .map(|path| (decode_path(path), size)),
+    );
+    drop(queue);
+
+    if !PREFETCH_RUNNING.swap(true, Ordering::AcqRel) {
+        let spawned = thread::Builder::new()
+            .name("thumbnail-prefetch".to_string())
+            .spawn(prefetch_worker);
+        if spawned.is_err() {
+            PREFETCH_RUNNING.store(false, Ordering::Release);
+        }
+    }
+}
+
+fn prefetch_worker() {
+    loop {
+        let next = PREFETCH_QUEUE.lock().unwrap().pop_front();
+        match next {
+            Some((path, size)) => {
+                let _ = thumbnail_for(&path, size);
+            }
+            None => {
+                PREFETCH_RUNNING.store(false, Ordering::Release);
+                // Paths queued between the empty pop and the store found the flag still
+                // set, take them over unless a new worker already did
+                if PREFETCH_QUEUE.lock().unwrap().is_empty()
+                    || PREFETCH_RUNNING.swap(true, Ordering::AcqRel)
+                {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Handler of the `thumbnail://` protocol, registered on the Tauri builder
+pub fn handle_protocol<R: Runtime>(
+    _ctx: UriSchemeContext<'_, R>,
+    request: Request<Vec<u8>>,
+    responder: UriSchemeResponder,
+) {
+    let uri_path = request.uri().path().to_string();
+
+    THUMBNAIL_POOL.spawn(move || {
+        let result = parse_protocol_path(&uri_path)
+            .and_then(|(path, size)| thumbnail_for(&path, size))
+            .and_then(|thumb| {
+                fs::read(&thumb).map_err(|e| format!("Failed to read thumbnail: {:?}", e))
+            });
+
+        let response = match result {
+            Ok(bytes) => Response::builder()
+                .header(CONTENT_TYPE, "image/png")
+                .body(bytes),
+            Err(message) => Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .header(CONTENT_TYPE, "text/plain")
+                .body(message.into_bytes()),
+        };
+        responder.respond(response.unwrap_or_default());
+    });
+}
+
+/// Splits `/<size>/<percent-encoded path>` into its parts
+fn parse_protocol_path(uri_path: &str) -> Result<(PathBuf, ThumbnailSize), String> {
+    let (size, encoded) = uri_path
+        .trim_start_matches('/')
+        .split_once('/')
+        .ok_or(format!("Invalid thumbnail request: {}", uri_path))?;
+    let size =
+        ThumbnailSize::from_dir_name(size).ok_or(format!("Unknown thumbnail size: {}", size))?;
+
+    Ok((bytes_to_path(percent_decode(encoded)), size))
+}
+
+/// Returns the cached thumbnail of `path`, generating it first if missing or stale.
+/// Cache entries follow the freedesktop spec: `<cache>/thumbnails/<size>/<md5(uri)>.png`
+/// tagged with `Thumb::URI` and `Thumb::MTime`.
+pub fn thumbnail_for(path: &Path, size: ThumbnailSize) -> Result<PathBuf, String> {
+    let path =
+        fs::canonicalize(path).map_err(|e| format!("Failed to resolve {:?}: {:?}", path, e))?;
+    let metadata =
+        fs::metadata(&path).map_err(|e| format!("Failed to read {:?}: {:?}", path, e))?;
+    let mtime = metadata
+        .modified()
+        .map(epoch_seconds)
+        .map_err(|e| format!("Failed to read modification time of {:?}: {:?}", path, e))?;
+
+    let root = dirs::cache_dir()
+        .ok_or("Could not determine cache directory".to_string())?
+        .join("thumbnails");
+    let uri = file_uri(&path);
+    let file_name = format!("{}.png", hex_digest(&uri));
+
+    let cached = root.join(size.dir_name()).join(&file_name);
+    if is_fresh(&cached, &uri, mtime) {
+        return Ok(cached);
+    }
+
+    let failed = root.join(FAIL_DIR).join(&file_name);
+    if is_fresh(&failed, &uri, mtime) {
+        return Err(format!(
+            "Thumbnail generation previously failed for {:?}",
+            path
+        ));
+    }
+
+    let image = image::ImageReader::open(&path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|e| format!("Failed to open {:?}: {:?}", path, e))
+        .and_then(|reader| {
+            reader
+                .decode()
+                .map_err(|e| format!("Failed to decode {:?}: {:?}", path, e))
+        });
+
+    let image = match image {
+        Ok(image) => image,
+        Err(e) => {
+            // Remember the failure so broken files aren't decoded over and over
+            let marker = image::RgbaImage::new(1, 1);
+            let _ = write_thumbnail(&failed, &marker, &uri, mtime, &metadata, (0, 0));
+            return Err(e);
+        }
+    };
+
+    // Never upscale: small pictures are stored at their original size
+    let max = size.pixels();
+    let original = (image.width(), image.height());
+    let thumbnail = if original.0 > max || original.1 > max {
+        image.thumbnail(max, max).into_rgba8()
+    } else {
+        image.into_rgba8()
+    };
+
+    write_thumbnail(&cached, &thumbnail, &uri, mtime, &metadata, original)?;
+    Ok(cached)
+}
+
+/// A cached file is valid as long as it was made for the same URI and modification time
+fn is_fresh(thumbnail: &Path, uri: &str, mtime: i64) -> bool {
+    let Ok(file) = File::open(thumbnail) else {
+        return false;
+    };
+    let Ok(reader) = png::Decoder::new(BufReader::new(file)).read_info() else {
+        return false;
+    };
+
+    let text: HashMap<&str, &str> = reader
+        .info()
+        .uncompressed_latin1_text
+        .iter()
+        .map(|chunk| (chunk.keyword.as_str(), chunk.text.as_str()))
+        .collect();
+
+    text.get("Thumb::URI") == Some(&uri)
+        && text.get("Thumb::MTime") == Some(&mtime.to_string().as_str())
+}
+
+fn write_thumbnail(
+    target: &Path,
+    image: &image::RgbaImage,
+    uri: &str,
+    mtime: i64,
+    metadata: &fs::Metadata,
+    original: (u32, u32),
+) -> Result<(), String> {
+    let dir = target
+        .parent()
+        .ok_or("Invalid thumbnail path".to_string())?;
+    create_private_dir(dir)?;
+
+    // Write to a temporary file and rename, so readers never see a partial thumbnail
+    let temp = dir.join(format!(
+        ".{}.{}.{}.tmp",
+        target.file_name().unwrap_or_default().to_string_lossy(),
+        std::process::id(),
+        NEXT_TEMP_ID.fetch_add(1, Ordering::Relaxed)
+    ));
+    let result = (|| {
+        let file = create_private_file(&temp)
+            .map_err(|e| format!("Failed to create thumbnail: {:?}", e))?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), image.width(), image.height());
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut text = vec![
+            ("Thumb::URI", uri.to_string()),
+            ("Thumb::MTime", mtime.to_string()),
+            ("Thumb::Size", metadata.len().to_string()),
+            ("Software", "file-explorer".to_string()),
+        ];
+        if original != (0, 0) {
+            text.push(("Thumb::Image::Width", original.0.to_string()));
+            text.push(("Thumb::Image::Height", original.1.to_string()));
+        }
+        for (keyword, value) in text {
+            encoder
+                .add_text_chunk(keyword.to_string(), value)
+                .map_err(|e| format!("Failed to write thumbnail: {:?}", e))?;
+        }
+
+        let mut writer = encoder
+            .write_header()
+            .map_err(|e| format!("Failed to write thumbnail: {:?}", e))?;
+        writer
+            .write_image_data(image.as_raw())
+            .map_err(|e| format!("Failed to write thumbnail: {:?}", e))?;
+        writer
+            .finish()
+            .map_err(|e| format!("Failed to write thumbnail: {:?}", e))
+    })();
+
+    match result {
+        Ok(()) => {
+            fs::rename(&temp, target).map_err(|e| format!("Failed to store thumbnail: {:?}", e))
+        }
+        Err(e) => {
+            let _ = fs::remove_file(&temp);
+            Err(e)
+        }
+    }
+}
+
+/// The spec requires thumbnail directories to be readable by the user only
+fn create_private_dir(dir: &Path) -> Result<(), String> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder
+        .create(dir)
+        .map_err(|e| format!("Failed to create thumbnail directory: {:?}", e))
+}
+
+/// Thumbnails reveal what private files contain, the spec requires them to be 0600
+fn create_private_file(path: &Path) -> io::Result<File> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
+fn hex_digest(uri: &str) -> String {
+    Md5::digest(uri.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+// ------------------- URI helpers -------------------
+
+/// Absolute `file://` URI with every byte outside the unreserved set percent-encoded
+pub fn file_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for &byte in path_to_bytes(path).iter() {
+        if byte.is_ascii_alphanumeric() || b"-_.!~*'()/".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    uri
+}
@@ -0,0 +1,279 @@
+use notify::{
+    event::{EventKind, ModifyKind, RenameMode},
+    RecommendedWatcher, RecursiveMode, Watcher,
+};
+use once_cell::sync::Lazy;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{mpsc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+use tauri::{command, AppHandle, Emitter};
+
+use crate::directories::build_file_info;
+use crate::format::Formatter;
+use crate::models::FileInfo;
+use crate::paths::{self, decode_path, encode_path};
+use crate::settings;
+
+/// Event emitted with the coalesced changes of a watched folder
+pub const DIRECTORY_CHANGED_EVENT: &str = "directory-changed";
+
+/// Changes are flushed once a folder has been quiet for this long...
+const DEBOUNCE: Duration = Duration::from_millis(200);
+/// ...or at the latest after this delay, so a busy download still refreshes the view
+const MAX_DELAY: Duration = Duration::from_secs(1);
+
+// Single inotify instance shared by every open folder, created on first use
+static WATCHER: Lazy<Mutex<Option<DirectoryWatcher>>> = Lazy::new(|| Mutex::new(None));
+
+struct DirectoryWatcher {
+    watcher: RecommendedWatcher,
+    // Number of views showing each folder, the watch is removed when it drops to zero
+    watched: HashMap<PathBuf, usize>,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct DirectoryChanges {
+    pub directory: String,
+    pub added: Vec<FileInfo>,
+    pub removed: Vec<String>,
+    pub changed: Vec<FileInfo>,
+    pub directory_removed: bool, // the watched folder itself was deleted or moved away
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Change {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl Change {
+    /// Folds a new event into the pending change of a path.
+    /// A file created and deleted within the same window produces no event at all.
+    fn merge(previous: Option<Change>, next: Change) -> Option<Change> {
+        match (previous, next) {
+            (None, next) => Some(next),
+            (Some(Change::Added), Change::Removed) => None,
+            (Some(Change::Added), _) => Some(Change::Added),
+            (Some(Change::Removed), Change::Removed) => Some(Change::Removed),
+            (Some(Change::Removed), _) => Some(Change::Changed),
+            (Some(Change::Changed), Change::Removed) => Some(Change::Removed),
+            (Some(Change::Changed), _) => Some(Change::Changed),
+        }
+    }
+}
+
+/// Starts reporting changes of the direct children of `path` through
+/// `directory-changed` events. Calls are counted, so two views of the same folder
+/// need two `unwatch_directory` calls.
+#[command]
+pub fn watch_directory(app: AppHandle, path: String) -> Result<(), String> {
+    let path = decode_path(&path);
+    if !path.is_dir() {
+        return Err(format!("Not a directory: {:?}", path));
+    }
+
+    let mut state = WATCHER.lock().unwrap();
+    if state.is_none() {
+        *state = Some(start_watcher(app)?);
+    }
+    let Some(state) = state.as_mut() else {
+        return Err("Directory watcher is not available".to_string());
+    };
+
+    if let Some(count) = state.watched.get_mut(&path) {
+        *count += 1;
+        return Ok(());
+    }
+    state
+        .watcher
+        .watch(&path, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch {:?}: {:?}", path, e))?;
+    state.watched.insert(path, 1);
+    Ok(())
+}
+
+/// Stops watching a folder previously passed to `watch_directory`
+#[command]
+pub fn unwatch_directory(path: String) -> Result<(), String> {
+    let path = decode_path(&path);
+    let mut state = WATCHER.lock().unwrap();
+    let Some(state) = state.as_mut() else {
+        return Ok(());
+    };
+
+    match state.watched.get_mut(&path) {
+        Some(count) if *count > 1 => *count -= 1,
+        Some(_) => {
+            state.watched.remove(&path);
+            // The watch is already gone if the folder was deleted
+            let _ = state.watcher.unwatch(&path);
+        }
+        None => {}
+    }
+    Ok(())
+}
+
+fn start_watcher(app: AppHandle) -> Result<DirectoryWatcher, String> {
+    let (sender, receiver) = mpsc::channel();
+    let watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+        if let Ok(event) = result {
+            let _ = sender.send(event);
+        }
+    })
+    .map_err(|e| format!("Failed to start directory watcher: {:?}", e))?;
+
+    thread::Builder::new()
+        .name("directory-watcher".to_string())
+        .spawn(move || debounce_events(app, receiver))
+        .map_err(|e| format!("Failed to start directory watcher: {:?}", e))?;
+
+    Ok(DirectoryWatcher {
+        watcher,
+        watched: HashMap::new(),
+    })
+}
+
+/// Collects raw events until the debounce window closes, then emits one event per folder
+fn debounce_events(app: AppHandle, receiver: mpsc::Receiver<notify::Event>) {
+    let mut pending: HashMap<PathBuf, Change> = HashMap::new();
+    let mut first_event: Option<Instant> = None;
+
+    loop {
+        let timeout = match first_event {
+            Some(first) => DEBOUNCE.min(MAX_DELAY.saturating_sub(first.elapsed())),
+            None => Duration::from_secs(3600),
+        };
+
+        match receiver.recv_timeout(timeout) {
+            Ok(event) => {
+                for (path, change) in classify(event) {
+                    match Change::merge(pending.get(&path).copied(), change) {
+                        Some(merged) => pending.insert(path, merged),
+                        None => pending.remove(&path),
+                    };
+                }
+                first_event.get_or_insert_with(Instant::now);
+                if first_event.is_some_and(|first| first.elapsed() < MAX_DELAY) {
+                    continue;
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => return,
+        }
+
+        if !pending.is_empty() {
+            flush(&app, std::mem::take(&mut pending));
+        }
+        first_event = None;
+    }
+}
+
+fn classify(event: notify::Event) -> Vec<(PathBuf, Change)> {
+    let change = match event.kind {
+        EventKind::Access(_) => return Vec::new(),
+        EventKind::Create(_) => Change::Added,
+        EventKind::Remove(_) => Change::Removed,
+        EventKind::Modify(ModifyKind::Name(RenameMode::From)) => Change::Removed,
+        EventKind::Modify(ModifyKind::Name(RenameMode::To)) => Change::Added,
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+            let mut paths = event.paths.into_iter();
+            return paths
+                .next()
+                .map(|from| (from, Change::Removed))
+                .into_iter()
+                .chain(paths.next().map(|to| (to, Change::Added)))
+                .collect();
+        }
+        // Unpaired renames: whether the path still exists tells which side we saw
+        EventKind::Modify(ModifyKind::Name(_)) => {
+            return event
+                .paths
+                .into_iter()
+                .map(|path| {
+                    let change = if fs::symlink_metadata(&path).is_ok() {
+                        Change::Added
+                    } else {
+                        Change::Removed
+                    };
+                    (path, change)
+                })
+                .collect();
+        }
+        _ => Change::Changed,
+    };
+    event.paths.into_iter().map(|path| (path, change)).collect()
+}
+
+fn flush(app: &AppHandle, pending: HashMap<PathBuf, Change>) {
+    let watched: Vec<PathBuf> = match WATCHER.lock().unwrap().as_ref() {
+        Some(state) => state.watched.keys().cloned().collect(),
+        None => return,
+    };
+
+    let show_hidden = settings::current().show_hidden_files;
+    let formatter = Formatter::current();
+    let mut changes: HashMap<PathBuf, DirectoryChanges> = HashMap::new();
+    for (path, change) in &pending {
+        if *change == Change::Removed && watched.contains(path) {
+            directory_changes(&mut changes, path).directory_removed = true;
+        }
+        let Some(directory) = path.parent().filter(|p| watched.iter().any(|w| w == p)) else {
+            continue;
+        };
+
+        // The file may be gone again by the time the window closes
+        let metadata = fs::symlink_metadata(path).ok();
+        // Hidden entries are left out of listings, removing one the view doesn't show is harmless
+        let hidden = !show_hidden
+            && path
+                .file_name()
+                .is_some_and(|name| paths::is_hidden(name, metadata.as_ref()));
+        if hidden && metadata.is_some() {
+            continue;
+        }
+        let entry = directory_changes(&mut changes, directory);
+        match (change, metadata) {
+            (Change::Removed, _) | (Change::Changed, None) => {
+                entry.removed.push(encode_path(path))
+            }
+            (Change::Added, None) => {}
+            (Change::Added, Some(metadata)) => {
+                entry.added.push(file_info(path, &metadata, &formatter))
+            }
+            (Change::Changed, Some(metadata)) => {
+                entry.changed.push(file_info(path, &metadata, &formatter))
+            }
+        }
+    }
+
+    for (_, directory_changes) in changes {
+        let _ = app.emit(DIRECTORY_CHANGED_EVENT, directory_changes);
+    }
+}
+
+fn directory_changes<'a>(
+    changes: &'a mut HashMap<PathBuf, DirectoryChanges>,
+    directory: &Path,
+) -> &'a mut DirectoryChanges {
+    changes
+        .entry(directory.to_path_buf())
+        .or_insert_with(|| DirectoryChanges {
+            directory: encode_path(directory),
+            added: Vec::new(),
+            removed: Vec::new(),
+            changed: Vec::new(),
+            directory_removed: false,
+        })
+}
+
+fn file_info(path: &Path, metadata: &fs::Metadata, formatter: &Formatter) -> FileInfo {
+    let mut info = build_file_info(paths::file_name(path), path, metadata, false, None);
+    info.format(formatter);
+    info
+}
@@ -0,0 +1,319 @@
+//! Runs the drive commands against a udisks2 mock on a private D-Bus daemon and a
+//! made up mount table, without touching a real device. Skipped when `dbus-daemon`
+//! isn't installed.
+//!
+//! | Device       | Drive    | Mount table                | udisks2 behavior          |
+//! |--------------|----------|----------------------------|---------------------------|
+//! | `/dev/mock0` | usb      | not mounted                | mounts on /media/mock/USB |
+//! | `/dev/mock1` | usb      | not mounted                | refuses to mount          |
+//! | `/dev/mock2` | -        | not mounted                | unknown device            |
+//! | `/dev/mock3` | usb      | `<temp>/usb`               | unmounts                  |
+//! | `/dev/mock4` | internal | `<temp>/data`              | busy on unmount           |
+
+#![cfg(target_os = "linux")]
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::{BufRead, BufReader, ErrorKind},
+    path::Path,
+    process::{Child, Command, Stdio},
+    sync::{Arc, Mutex},
+};
+
+use file_explorer_lib::{drives, ExplorerError};
+use zbus::{
+    fdo, interface,
+    zvariant::{OwnedObjectPath, OwnedValue},
+};
+
+const SERVICE: &str = "org.freedesktop.UDisks2";
+const MANAGER_PATH: &str = "/org/freedesktop/UDisks2/Manager";
+const BLOCK_PREFIX: &str = "/org/freedesktop/UDisks2/block_devices/";
+const DRIVE_PREFIX: &str = "/org/freedesktop/UDisks2/drives/";
+
+/// Methods called on the mock, e.g. "Unmount mock3" or "PowerOff usb"
+type CallLog = Arc<Mutex<Vec<String>>>;
+
+#[derive(Debug, zbus::DBusError)]
+#[zbus(prefix = "org.freedesktop.UDisks2.Error")]
+enum MockError {
+    #[zbus(error)]
+    ZBus(zbus::Error),
+    Failed(String),
+    DeviceBusy(String),
+}
+
+fn object_path(path: String) -> OwnedObjectPath {
+    OwnedObjectPath::try_from(path).unwrap()
+}
+
+struct Manager {
+    devices: Vec<&'static str>, // kernel names, e.g. "mock0"
+}
+
+#[interface(name = "org.freedesktop.UDisks2.Manager")]
+impl Manager {
+    fn resolve_device(
+        &self,
+        devspec: HashMap<String, OwnedValue>,
+        _options: HashMap<String, OwnedValue>,
+    ) -> fdo::Result<Vec<OwnedObjectPath>> {
+        let path = devspec
+            .get("path")
+            .and_then(|path| path.downcast_ref::<&str>().ok())
+            .ok_or_else(|| fdo::Error::InvalidArgs("Only paths are supported".into()))?;
+        let name = path.strip_prefix("/dev/").unwrap_or(path);
+        if !self.devices.contains(&name) {
+            return Ok(Vec::new());
+        }
+        Ok(vec![object_path(format!("{}{}", BLOCK_PREFIX, name))])
+    }
+}
+
+struct Block {
+    drive: &'static str,
+}
+
+#[interface(name = "org.freedesktop.UDisks2.Block")]
+impl Block {
+    #[zbus(property)]
+    fn drive(&self) -> OwnedObjectPath {
+        object_path(format!("{}{}", DRIVE_PREFIX, self.drive))
+    }
+}
+
+struct Filesystem {
+    name: &'static str,
+    mount_point: Option<&'static str>, // None makes Mount fail
+    busy: bool,
+    log: CallLog,
+}
+
+#[interface(name = "org.freedesktop.UDisks2.Filesystem")]
+impl Filesystem {
+    fn mount(&self, _options: HashMap<String, OwnedValue>) -> Result<String, MockError> {
+        self.log
+            .lock()
+            .unwrap()
+            .push(format!("Mount {}", self.name));
+        self.mount_point
+            .map(str::to_string)
+            .ok_or_else(|| MockError::Failed("Mock device refuses to mount".into()))
+    }
+
+    fn unmount(&self, _options: HashMap<String, OwnedValue>) -> Result<(), MockError> {
+        self.log
+            .lock()
+            .unwrap()
+            .push(format!("Unmount {}", self.name));
+        match self.busy {
+            true => Err(MockError::DeviceBusy("target is busy".into())),
+            false => Ok(()),
+        }
+    }
+}
+
+struct Drive {
+    name: &'static str,
+    removable: bool,
+    log: CallLog,
+}
+
+#[interface(name = "org.freedesktop.UDisks2.Drive")]
+impl Drive {
+    #[zbus(property)]
+    fn removable(&self) -> bool {
+        self.removable
+    }
+
+    #[zbus(property)]
+    fn ejectable(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn can_power_off(&self) -> bool {
+        true
+    }
+
+    fn power_off(&self, _options: HashMap<String, OwnedValue>) {
+        self.log
+            .lock()
+            .unwrap()
+            .push(format!("PowerOff {}", self.name));
+    }
+}
+
+/// Kills the process when the test ends, even on a failed assertion
+struct Process(Child);
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+fn calls(log: &CallLog, call: &str) -> usize {
+    log.lock().unwrap().iter().filter(|c| *c == call).count()
+}
+
+// One test, the bus and the mount table are passed to the commands through the environment
+#[tokio::test(flavor = "multi_thread")]
+async fn drive_commands_against_udisks_mock() {
+    let daemon = match Command::new("dbus-daemon")
+        .args(["--session", "--nofork", "--print-address"])
+        .stdout(Stdio::piped())
+        .spawn()
+    {
+        Ok(daemon) => daemon,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            eprintln!("dbus-daemon is not installed, skipping the udisks2 mock test");
+            return;
+        }
+        Err(e) => panic!("Failed to start dbus-daemon: {}", e),
+    };
+    let mut daemon = Process(daemon);
+    let mut address = String::new();
+    let stdout = daemon.0.stdout.take().unwrap();
+    BufReader::new(stdout).read_line(&mut address).unwrap();
+    let address = address.trim().to_string();
+
+    let temp = std::env::temp_dir().join(format!("udisks-mock-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&temp);
+    fs::create_dir_all(temp.join("usb")).unwrap();
+    fs::create_dir_all(temp.join("data")).unwrap();
+    let temp = fs::canonicalize(&temp).unwrap();
+    let usb = temp.join("usb").display().to_string();
+    let data = temp.join("data").display().to_string();
+    let mount_table = temp.join("mountinfo");
+    fs::write(
+        &mount_table,
+        format!(
+            "1 0 8:1 / / rw - ext4 /dev/sda1 rw\n\
+             40 1 8:17 / {} rw,nosuid - vfat /dev/mock3 rw\n\
+             41 1 8:33 / {} rw - ext4 /dev/mock4 rw\n",
+            usb, data
+        ),
+    )
+    .unwrap();
+
+    let log = CallLog::default();
+    let filesystem = |name, mount_point, busy| Filesystem {
+        name,
+        mount_point,
+        busy,
+        log: log.clone(),
+    };
+    let mut builder = zbus::connection::Builder::address(address.as_str())
+        .unwrap()
+        .name(SERVICE)
+        .unwrap()
+        .serve_at(
+            MANAGER_PATH,
+            Manager {
+                devices: vec!["mock0", "mock1", "mock3", "mock4"],
+            },
+        )
+        .unwrap();
+    let devices = [
+        (
+            "mock0",
+            "usb",
+            filesystem("mock0", Some("/media/mock/USB"), false),
+        ),
+        ("mock1", "usb", filesystem("mock1", None, false)),
+        ("mock3", "usb", filesystem("mock3", None, false)),
+        ("mock4", "internal", filesystem("mock4", None, true)),
+    ];
+    for (name, drive, filesystem) in devices {
+        let path = object_path(format!("{}{}", BLOCK_PREFIX, name));
+        builder = builder
+            .serve_at(path.clone(), Block { drive })
+            .unwrap()
+            .serve_at(path, filesystem)
+            .unwrap();
+    }
+    for (name, removable) in [("usb", true), ("internal", false)] {
+        let drive = Drive {
+            name,
+            removable,
+            log: log.clone(),
+        };
+        builder = builder
+            .serve_at(format!("{}{}", DRIVE_PREFIX, name), drive)
+            .unwrap();
+    }
+    let _mock = builder.build().await.unwrap();
+    std::env::set_var(drives::BUS_ADDRESS_VARIABLE, &address);
+    std::env::set_var(drives::MOUNT_TABLE_VARIABLE, &mount_table);
+
+    // Mount
+    let mounted = drives::mount_drive("/dev/mock0".to_string()).await;
+    assert_eq!(mounted, Ok("/media/mock/USB".to_string()));
+    let refused = drives::mount_drive("/dev/mock1".to_string()).await;
+    assert!(
+        matches!(refused, Err(ExplorerError::Io { .. })),
+        "{:?}",
+        refused
+    );
+    let unknown = drives::mount_drive("/dev/mock2".to_string()).await;
+    assert!(unknown.is_err(), "{:?}", unknown);
+    // Already mounted devices are not mounted again
+    let existing = drives::mount_drive("/dev/mock3".to_string()).await;
+    assert_eq!(existing, Ok(usb.clone()));
+    assert_eq!(calls(&log, "Mount mock3"), 0);
+
+    // Unmount
+    assert_eq!(drives::unmount_drive(usb.clone()).await, Ok(()));
+    assert_eq!(calls(&log, "Unmount mock3"), 1);
+    let not_mounted = drives::unmount_drive(temp.display().to_string()).await;
+    assert!(matches!(
+        not_mounted,
+        Err(ExplorerError::InvalidArgument(_))
+    ));
+    let root = drives::unmount_drive("/".to_string()).await;
+    assert!(matches!(root, Err(ExplorerError::InvalidArgument(_))));
+
+    // A busy filesystem reports who is using it
+    let holder = Process(
+        Command::new("sleep")
+            .arg("60")
+            .current_dir(Path::new(&data))
+            .spawn()
+            .unwrap(),
+    );
+    let busy = drives::unmount_drive(data.clone()).await;
+    match busy {
+        Err(ExplorerError::Busy { path, processes }) => {
+            assert_eq!(path, data);
+            assert!(
+                processes.iter().any(|p| p.pid == holder.0.id()),
+                "{:?}",
+                processes
+            );
+        }
+        other => panic!("Expected a busy error, got {:?}", other),
+    }
+    drop(holder);
+
+    // Internal drives are refused before any of their filesystems is unmounted
+    let unmounts = calls(&log, "Unmount mock4");
+    let internal = drives::eject_drive("/dev/mock4".to_string()).await;
+    assert!(
+        matches!(internal, Err(ExplorerError::InvalidArgument(_))),
+        "{:?}",
+        internal
+    );
+    assert_eq!(calls(&log, "Unmount mock4"), unmounts);
+    assert_eq!(calls(&log, "PowerOff internal"), 0);
+
+    // Removable drives are unmounted, then powered off
+    assert_eq!(drives::eject_drive("/dev/mock0".to_string()).await, Ok(()));
+    assert_eq!(calls(&log, "Unmount mock3"), 2);
+    assert_eq!(calls(&log, "Unmount mock4"), unmounts);
+    assert_eq!(calls(&log, "PowerOff usb"), 1);
+
+    let _ = fs::remove_dir_all(&temp);
+}
@@ -58,7 +58,7 @@ const Downloads = () => {
                     </span>
                   </td>
                   <td className={styles.cellModified}>
-                    <span>{formatDate(file.modification_date)}</span>
+                    <span>{file.formatted_date || formatDate(file.modification_date)}</span>
                   </td>
                   <td className={styles.cellSize}>
                     <span>{file.formatted_size || "—"}</span>
@@ -64,7 +64,7 @@ const Pictures = () => {
                     </span>
                   </td>
                   <td className={styles.cellModified}>
-                    <span>{formatDate(picture.modification_date)}</span>
+                    <span>{picture.formatted_date || formatDate(picture.modification_date)}</span>
                   </td>
                   <td className={styles.cellSize}>
                     <span>{picture.formatted_size || "—"}</span>
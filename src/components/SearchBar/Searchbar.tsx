@@ -282,7 +282,7 @@ const Searchbar = () => {
                         {result.formatted_size}
                       </span>
                       <span className={styles.resultDate}>
-                        {formatDate(result.modification_date)}
+                        {result.formatted_date || formatDate(result.modification_date)}
                       </span>
                     </div>
                   </div>
@@ -1,4 +1,4 @@
-import { useNavigate } from "react-router-dom";
+import { useLocation, useNavigate } from "react-router-dom";
 import {
   createContext,
   useState,
@@ -21,11 +21,23 @@ interface FileInfo {
   file_size: number;
   modification_date: string;
   formatted_size: string;
+  formatted_date: string; // follows the date format setting, empty when unknown
   file_path: string;
   file_type: string; // "file", "directory", "symlink", or "unknown"
   image?: string;
 }
 
+interface SkipSummary {
+  total: number;
+  counts: Record<string, number>; // "permission_denied", "broken_symlink", "io"
+  entries: { path: string; reason: string; message: string }[];
+}
+
+interface DirectoryListing {
+  files: FileInfo[];
+  skipped: SkipSummary;
+}
+
 interface DiskInfo {
   name: string;
   kind: string;
@@ -62,6 +74,14 @@ interface AppContextType {
   setViewMode: (mode: ViewMode) => void;
 }
 
+// Listing command behind each view. It runs while the view is shown and is
+// cancelled when the user navigates away; the command name is its request id.
+const VIEW_LISTINGS: Record<string, string> = {
+  "/Downloads": "list_downloads",
+  "/Documents": "list_documents",
+  "/Pictures": "list_pictures",
+};
+
 // -------------------- Reducer --------------------
 
 type State = {
@@ -143,6 +163,7 @@ export const AppProvider = ({ children }: { children: ReactNode }) => {
   const [searchError, setSearchError] = useState<string | null>(null);
   const searchAbortControllerRef = useRef<AbortController | null>(null);
   const navigate = useNavigate();
+  const location = useLocation();
 
   // -------------------- Helpers --------------------
 
@@ -158,6 +179,23 @@ export const AppProvider = ({ children }: { children: ReactNode }) => {
     []
   );
 
+  // Resolves to null when the listing was cancelled
+  const invokeListing = useCallback(
+    async (command: string): Promise<DirectoryListing | null> => {
+      try {
+        // One request id per command: refetching a view cancels its previous listing
+        return await invoke<DirectoryListing>(command, { requestId: command });
+      } catch (error: any) {
+        if (error?.code === "cancelled") {
+          return null;
+        }
+        console.error(`Error invoking ${command}:`, error);
+        throw error;
+      }
+    },
+    []
+  );
+
   // -------------------- Search --------------------
 
   const performSearch = useCallback(async (query: string) => {
@@ -298,19 +336,37 @@ export const AppProvider = ({ children }: { children: ReactNode }) => {
   }, [invokeTauriCommand]);
 
   const fetchDownloads = useCallback(async () => {
-    const result = (await invokeTauriCommand("list_downloads")) as FileInfo[];
-    dispatch({ type: "SET_DOWNLOADS", payload: result });
-  }, [invokeTauriCommand]);
+    const result = await invokeListing("list_downloads");
+    if (!result) {
+      return;
+    }
+    if (result.skipped.total > 0) {
+      console.warn(`Skipped ${result.skipped.total} entries in downloads`, result.skipped);
+    }
+    dispatch({ type: "SET_DOWNLOADS", payload: result.files });
+  }, [invokeListing]);
 
   const fetchDocuments = useCallback(async () => {
-    const result = (await invokeTauriCommand("list_documents")) as FileInfo[];
-    dispatch({ type: "SET_DOCUMENTS", payload: result });
-  }, [invokeTauriCommand]);
+    const result = await invokeListing("list_documents");
+    if (!result) {
+      return;
+    }
+    if (result.skipped.total > 0) {
+      console.warn(`Skipped ${result.skipped.total} entries in documents`, result.skipped);
+    }
+    dispatch({ type: "SET_DOCUMENTS", payload: result.files });
+  }, [invokeListing]);
 
   const fetchPictures = useCallback(async () => {
-    const result = (await invokeTauriCommand("list_pictures")) as FileInfo[];
-    dispatch({ type: "SET_PICTURES", payload: result });
-  }, [invokeTauriCommand]);
+    const result = await invokeListing("list_pictures");
+    if (!result) {
+      return;
+    }
+    if (result.skipped.total > 0) {
+      console.warn(`Skipped ${result.skipped.total} entries in pictures`, result.skipped);
+    }
+    dispatch({ type: "SET_PICTURES", payload: result.files });
+  }, [invokeListing]);
 
   // -------------------- Lifecycle --------------------
 
@@ -327,10 +383,29 @@ export const AppProvider = ({ children }: { children: ReactNode }) => {
 
   useEffect(() => {
     fetchDisks();
-    fetchDownloads();
-    fetchDocuments();
-    fetchPictures();
-  }, [fetchDisks, fetchDownloads, fetchDocuments, fetchPictures]);
+  }, [fetchDisks]);
+
+  // Lists the folder of the current view, and stops that listing once the user
+  // navigates to another view before it completed
+  useEffect(() => {
+    const command = VIEW_LISTINGS[location.pathname];
+    if (!command) {
+      return;
+    }
+    const fetchers: Record<string, () => Promise<void>> = {
+      list_downloads: fetchDownloads,
+      list_documents: fetchDocuments,
+      list_pictures: fetchPictures,
+    };
+    // Failures are already logged by invokeListing
+    fetchers[command]().catch(() => {});
+
+    return () => {
+      invoke("cancel_listing", { requestId: command }).catch((error) =>
+        console.error("Error cancelling listing:", error)
+      );
+    };
+  }, [location.pathname, fetchDownloads, fetchDocuments, fetchPictures]);
 
   // -------------------- Context Value --------------------
 
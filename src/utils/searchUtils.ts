@@ -4,6 +4,7 @@ interface FileInfo {
   file_size: number;
   modification_date: string;
   formatted_size: string;
+  formatted_date: string;
   file_path: string;
   file_type: string;
 }